@@ -0,0 +1,198 @@
+//! Animated textures the way a `.png.mcmeta` next to them describes, a grid of frames (usually a vertical strip) that
+//! get played a game tick, 1/20th of a second, at a time.
+//!
+//! The atlas only ever holds one frame of each, the first one played, the rest are kept aside in an
+//! [`AtlasAnimation`] for [`McModelMesh::texture_at_tick`](crate::McModelMesh::texture_at_tick) to swap in.
+
+use anyhow::Context;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+/// Game ticks in a second, which is what frame times count in
+pub const TICKS_PER_SECOND: u32 = 20;
+
+/// The `animation` section of a `.mcmeta`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct McTextureAnimation {
+	/// Blend between frames over their time instead of switching from one to the next
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub interpolate: bool,
+	/// Size of a frame, see [`McTextureAnimation::frame_size`] for when they're left out
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub width: Option<u32>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub height: Option<u32>,
+	/// Ticks each frame shows for unless it says otherwise
+	#[serde(default = "McTextureAnimation::default_frametime")]
+	pub frametime: u32,
+	/// The order the frames play in, every frame once from the top when left out
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub frames: Option<Vec<McAnimationFrame>>,
+}
+
+/// One entry of [`McTextureAnimation::frames`], either just the frame's index or that with its own time
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum McAnimationFrame {
+	Index(u32),
+	Timed { index: u32, time: u32 },
+}
+
+#[derive(Deserialize)]
+struct McTextureMeta {
+	animation: Option<McTextureAnimation>,
+}
+
+impl McTextureAnimation {
+	fn default_frametime() -> u32 {
+		1
+	}
+
+	/// The animation in a `.mcmeta` file's contents, `None` if it has no `animation` section as it can be there for
+	/// other things like `texture.blur`
+	pub fn from_mcmeta(data: &[u8]) -> anyhow::Result<Option<McTextureAnimation>> {
+		let meta: McTextureMeta = serde_json::from_slice(data).context("invalid texture mcmeta")?;
+		Ok(meta.animation)
+	}
+
+	/// Size of a frame for a `width` by `height` image: what's given, the whole image's extent for a side that isn't,
+	/// or squares as large as fit across the image when neither is
+	pub fn frame_size(&self, width: u32, height: u32) -> (u32, u32) {
+		match (self.width, self.height) {
+			(Some(frame_width), Some(frame_height)) => (frame_width, frame_height),
+			(Some(frame_width), None) => (frame_width, height),
+			(None, Some(frame_height)) => (width, frame_height),
+			(None, None) => (width.min(height), width.min(height)),
+		}
+	}
+
+	/// The frames as they play, each frame's index into the image's grid and the ticks it shows for, leaving out any
+	/// the image doesn't have
+	pub fn schedule(&self, frame_count: u32) -> Vec<(u32, u32)> {
+		let frametime = self.frametime.max(1);
+		match &self.frames {
+			None => (0..frame_count).map(|index| (index, frametime)).collect(),
+			Some(frames) => frames
+				.iter()
+				.map(|frame| match *frame {
+					McAnimationFrame::Index(index) => (index, frametime),
+					McAnimationFrame::Timed { index, time } => (index, time.max(1)),
+				})
+				.filter(|&(index, _)| index < frame_count)
+				.collect(),
+		}
+	}
+}
+
+/// Ticks until all of `animations` are back at their first frame at the same time, 1 without any, saturating rather
+/// than overflowing for animations that hardly ever line up
+pub fn common_period<'a>(animations: impl IntoIterator<Item = &'a AtlasAnimation>) -> u32 {
+	fn gcd(a: u64, b: u64) -> u64 {
+		if b == 0 {
+			a
+		} else {
+			gcd(b, a % b)
+		}
+	}
+	let period = animations
+		.into_iter()
+		.map(|animation| animation.period() as u64)
+		.fold(1, |lcm, period| (lcm / gcd(lcm, period) * period).min(u32::MAX as u64));
+	period as u32
+}
+
+/// A texture on the atlas that animates, with its frames cut out of its image
+#[derive(Clone, Debug)]
+pub struct AtlasAnimation {
+	/// The `[x, y, width, height]` rectangle of the atlas it's drawn into, the same as in
+	/// [`McModelMesh::atlas_mappings`](crate::McModelMesh::atlas_mappings)
+	pub rect: [u32; 4],
+	pub frames: Vec<RgbaImage>,
+	/// Indices into `frames` in the order they play, with the ticks each shows for, never empty
+	pub schedule: Vec<(usize, u32)>,
+	pub interpolate: bool,
+	/// The edge pixels repeated around it on the atlas, which need to follow the frame too
+	pub(crate) padding: u32,
+}
+
+impl AtlasAnimation {
+	/// Cuts `image` into frames, `None` if it has less than two of them to play so there's nothing to animate
+	pub fn new(image: &RgbaImage, animation: &McTextureAnimation) -> Option<AtlasAnimation> {
+		let (frame_width, frame_height) = animation.frame_size(image.width(), image.height());
+		if frame_width == 0 || frame_height == 0 {
+			return None;
+		}
+		let columns = image.width() / frame_width;
+		let frames = (0..columns * (image.height() / frame_height))
+			.map(|index| {
+				image::imageops::crop_imm(
+					image,
+					index % columns * frame_width,
+					index / columns * frame_height,
+					frame_width,
+					frame_height,
+				)
+				.to_image()
+			})
+			.collect::<Vec<_>>();
+		let schedule = animation
+			.schedule(frames.len() as u32)
+			.into_iter()
+			.map(|(index, time)| (index as usize, time))
+			.collect::<Vec<_>>();
+		if schedule.len() < 2 {
+			return None;
+		}
+		Some(AtlasAnimation {
+			rect: [0, 0, frame_width, frame_height],
+			frames,
+			schedule,
+			interpolate: animation.interpolate,
+			padding: 0,
+		})
+	}
+
+	/// Ticks for the whole animation to play once
+	pub fn period(&self) -> u32 {
+		self.schedule.iter().map(|(_, time)| time).sum()
+	}
+
+	/// The first frame played, which is what gets packed onto the atlas
+	pub fn first_frame(&self) -> &RgbaImage {
+		&self.frames[self.schedule[0].0]
+	}
+
+	/// Which entry of the schedule is showing `tick` ticks in and how many ticks into it that is
+	fn position(&self, tick: u32) -> (usize, u32) {
+		let mut tick = tick % self.period();
+		for (entry, (_, time)) in self.schedule.iter().enumerate() {
+			if tick < *time {
+				return (entry, tick);
+			}
+			tick -= time;
+		}
+		unreachable!("the tick was wrapped into the period")
+	}
+
+	/// Whether what shows at `tick` differs from the tick before, which with interpolation is any tick
+	pub fn changes_at(&self, tick: u32) -> bool {
+		let (entry, into) = self.position(tick);
+		into == 0 || (self.interpolate && self.schedule[entry].0 != self.schedule[(entry + 1) % self.schedule.len()].0)
+	}
+
+	/// What the texture looks like `tick` ticks in
+	pub fn frame_at(&self, tick: u32) -> RgbaImage {
+		let (entry, into) = self.position(tick);
+		let (index, time) = self.schedule[entry];
+		let frame = &self.frames[index];
+		if !self.interpolate || into == 0 {
+			return frame.clone();
+		}
+		let next = &self.frames[self.schedule[(entry + 1) % self.schedule.len()].0];
+		let t = into as f32 / time as f32;
+		RgbaImage::from_fn(frame.width(), frame.height(), |x, y| {
+			let (a, b) = (frame.get_pixel(x, y).0, next.get_pixel(x, y).0);
+			image::Rgba([0, 1, 2, 3].map(|c| (a[c] as f32 * (1.0 - t) + b[c] as f32 * t).round() as u8))
+		})
+	}
+}
@@ -1,8 +1,24 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
+use anyhow::Context as AnyContext;
 use clap::{Parser, ValueEnum};
+use mc_json_stuff::render::{self, OffscreenTarget};
+use mc_json_stuff::animation::{self, TICKS_PER_SECOND};
+use mc_json_stuff::pack::{LayeredResourcePack, ResourcePack};
+use mc_json_stuff::templates::{BuiltinTemplates, WithBuiltinTemplates};
+use mc_json_stuff::{
+	FsTextureSource, McBlockstateRotation, McModelDisplay, McModelGuiLight, McModelJson, McModelMesh, McModelTransform,
+	McResourceLocation, MeshOptions, ModelSource, TextureSource, TEXTURE_EXTENSIONS,
+};
 use three_d::*;
 
+const WINDOW_TITLE: &str = "MC JSON Renderer";
+
+/// How far `--animate` plays animations that take longer to all line up again
+const MAX_ANIMATION_TICKS: u32 = 60 * TICKS_PER_SECOND;
+
 #[derive(ValueEnum, Clone, Debug)]
 enum ArgCamera {
 	#[value()]
@@ -11,171 +27,515 @@ enum ArgCamera {
 	Perspective,
 	#[value()]
 	Wiki,
+	/// Matches the in-game inventory icon look, using the model's `gui` display transform
+	#[value()]
+	Inventory,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum ArgAlpha {
+	/// Pick from the textures, opaque if none have any transparency, cutout if it's only ever fully transparent,
+	/// blending otherwise
+	#[value()]
+	Auto,
+	#[value()]
+	Opaque,
+	/// Alpha testing like vanilla's leaves and plants, see `--alpha-cutout`
+	#[value()]
+	Cutout,
+	#[value()]
+	Blend,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ArgFormat {
+	#[value()]
+	Png,
+	/// Without transparency, see `--quality`
+	#[value(alias = "jpg")]
+	Jpeg,
+	/// Lossless, the only kind there's an encoder for
+	#[value()]
+	Webp,
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+enum ArgLighting {
+	/// Minecraft's per-direction face shading baked into the model, unlit
+	#[value()]
+	Baked,
+	/// Lit by a directional and an ambient light instead, see `--light-direction`
+	#[value()]
+	Phong,
 }
 
 #[derive(Parser, Clone, Debug)]
 struct Args {
-	/// Minecraft json model file to display
-	#[clap()]
-	pub json_file: PathBuf,
+	/// Minecraft json model files to display, or with `--pack` the models' ids in the pack such as `block/stone`,
+	/// several of them are shown together in the same scene, see `--at`
+	#[clap(required = true)]
+	pub json_files: Vec<PathBuf>,
+	/// Where to place a model in the scene in blocks as `x,y,z`, given once for each model in the same order as them.
+	/// Without it the models are lined up along X one block apart
+	#[clap(long, value_parser = parse_vec3, allow_hyphen_values = true)]
+	pub at: Vec<Vec3>,
+	/// Resource pack directory, zip, or game jar to load the model, its parents, and its textures from, can be given
+	/// several times to stack packs with the first one taking priority like the top of the in-game pack list
+	#[clap(long)]
+	pub pack: Vec<PathBuf>,
+	/// Replace a texture variable of the models as `id=texture`, the texture being an image file or anything the
+	/// model's `textures` could say like `block/dirt` or `#top`, can be given several times
+	#[clap(long, value_parser = parse_texture_override)]
+	pub set_texture: Vec<(String, String)>,
+	/// Turn the models around X by this many degrees the way a blockstate variant's `x` does, before `--rotate-y`
+	#[clap(long, default_value = "0", value_parser = parse_quarter_turn)]
+	pub rotate_x: i16,
+	/// Turn the models around Y by this many degrees the way a blockstate variant's `y` does
+	#[clap(long, default_value = "0", value_parser = parse_quarter_turn)]
+	pub rotate_y: i16,
+	/// Keep the textures from turning along with `--rotate-x` and `--rotate-y`, like a blockstate variant's `uvlock`
+	#[clap(long)]
+	pub uvlock: bool,
 	/// Camera field type to use
 	#[clap(value_enum, short, long, default_value = "perspective")]
 	pub camera: ArgCamera,
 	/// Path to immediately save a screenshot to upon open
 	#[clap(short, long)]
 	pub screenshot: Option<PathBuf>,
+	/// Image format to save screenshots in instead of going by the file extension
+	#[clap(value_enum, long)]
+	pub format: Option<ArgFormat>,
+	/// JPEG quality of screenshots from 1 to 100
+	#[clap(long, default_value = "90", value_parser = clap::value_parser!(u8).range(1..=100))]
+	pub quality: u8,
 	/// Window width
 	#[clap(long, default_value = "640")]
 	pub width: u32,
 	/// Window height
 	#[clap(long, default_value = "640")]
 	pub height: u32,
+	/// Render this many screenshots evenly spaced around the model, written as `name_000.png` and so on, or as a
+	/// single animated image if the screenshot path ends in `.gif`
+	#[clap(long)]
+	pub turntable: Option<u32>,
+	/// Save the model's animated textures playing in real time as an animated `.gif`, a frame for each change over
+	/// one whole cycle of them all (up to a minute of it), or a single frame if nothing animates
+	#[clap(long, conflicts_with_all = ["screenshot", "watch", "list_textures"])]
+	pub animate: Option<PathBuf>,
+	/// Render screenshots at this many times the resolution and downscale them to smooth out jagged edges
+	#[clap(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..=16))]
+	pub ssaa: u32,
+	/// Camera position as `x,y,z` in model units instead of the `--camera` preset's, with `--fit` only the direction
+	/// from the target matters
+	#[clap(long, value_parser = parse_vec3, allow_hyphen_values = true)]
+	pub eye: Option<Vec3>,
+	/// Point the camera looks at as `x,y,z` instead of the preset's, `--fit` moves it to the model's center
+	#[clap(long, value_parser = parse_vec3, allow_hyphen_values = true)]
+	pub target: Option<Vec3>,
+	/// Camera up direction as `x,y,z` instead of the preset's
+	#[clap(long, value_parser = parse_vec3, allow_hyphen_values = true)]
+	pub up: Option<Vec3>,
+	/// Vertical field of view of the perspective camera in degrees
+	#[clap(long, default_value = "60")]
+	pub fov: f32,
+	/// Distance to the near clipping plane
+	#[clap(long, default_value = "0.1")]
+	pub near: f32,
+	/// Distance to the far clipping plane, by default 256 or however far `--fit` needs
+	#[clap(long)]
+	pub far: Option<f32>,
+	/// Height of the view of the orthographic cameras in model units, by default 32, 16 for `inventory`, or
+	/// whatever `--fit` works out
+	#[clap(long)]
+	pub ortho_scale: Option<f32>,
+	/// Center the camera on the model and zoom so that the whole model fits in view
+	#[clap(long)]
+	pub fit: bool,
+	/// Frame a full 0-16 block so it's exactly as tall as the image and centered in it, the way the Minecraft Wiki's
+	/// renders are, so they can go in an infobox without any rescaling.  Always at that same scale whatever size the
+	/// model is, best with `--camera wiki` and a power of two `--height`
+	#[clap(long, conflicts_with_all = ["fit", "ortho_scale", "target"])]
+	pub wiki_exact: bool,
+	/// How much of the frame the model should fill when using `--fit`
+	#[clap(long, default_value = "0.8")]
+	pub fit_fraction: f32,
+	/// Background color as `#rrggbb`, `#rrggbbaa`, or `transparent`
+	#[clap(long, default_value = "transparent", value_parser = parse_background)]
+	pub background: Srgba,
+	/// Draw faces with translucent texels separately after the opaque ones, which avoids sorting artifacts on mostly
+	/// opaque models with a few see-through faces
+	#[clap(long, conflicts_with = "alpha")]
+	pub split_translucent: bool,
+	/// Print each texture variable, what it resolves to, where that's loaded from and whether it loaded, then exit
+	/// without opening a window
+	#[clap(long)]
+	pub list_textures: bool,
+	/// How the model gets shaded
+	#[clap(value_enum, long, default_value = "baked")]
+	pub lighting: ArgLighting,
+	/// Direction the directional light shines in as `x,y,z` for `--lighting phong`
+	#[clap(long, default_value = "-0.4,-1,-0.7", value_parser = parse_vec3, allow_hyphen_values = true)]
+	pub light_direction: Vec3,
+	/// Intensity of the directional light for `--lighting phong`
+	#[clap(long, default_value = "1.5")]
+	pub light_intensity: f32,
+	/// Intensity of the ambient light for `--lighting phong`
+	#[clap(long, default_value = "0.5")]
+	pub ambient_intensity: f32,
+	/// Draw the X, Y and Z axes in red, green and blue from the origin and a 16x16 grid on the ground under the model,
+	/// left out of screenshots unless `--screenshot-axes` is given too
+	#[clap(long)]
+	pub show_axes: bool,
+	/// Keep the `--show-axes` helpers in screenshots as well
+	#[clap(long, requires = "show_axes")]
+	pub screenshot_axes: bool,
+	/// Save the packed texture atlas to this path, to check what got packed where, numbered like `name_000.png` for
+	/// each model when there are several
+	#[clap(long)]
+	pub dump_atlas: Option<PathBuf>,
+	/// How texture transparency gets drawn
+	#[clap(value_enum, long, default_value = "auto")]
+	pub alpha: ArgAlpha,
+	/// Alpha below which fragments are discarded when drawing with cutout
+	#[clap(long, default_value_t = render::DEFAULT_ALPHA_CUTOUT)]
+	pub alpha_cutout: f32,
+	/// Reload the models in the window whenever one of their files or textures changes, keeping the camera where it
+	/// is.  Models that fail to load are reported and the last ones that did stay up
+	#[clap(long, conflicts_with_all = ["screenshot", "list_textures"])]
+	pub watch: bool,
+}
+
+impl Args {
+	fn blockstate_rotation(&self) -> McBlockstateRotation {
+		McBlockstateRotation { x: self.rotate_x, y: self.rotate_y, uvlock: self.uvlock }
+	}
+}
+
+fn parse_background(background: &str) -> Result<Srgba, String> {
+	if background.eq_ignore_ascii_case("transparent") {
+		return Ok(Srgba::new(0, 0, 0, 0));
+	}
+	let hex = background.strip_prefix('#').unwrap_or(background);
+	let channel = |idx: usize| {
+		hex.get(idx * 2..idx * 2 + 2)
+			.and_then(|c| u8::from_str_radix(c, 16).ok())
+			.ok_or_else(|| {
+				format!("invalid background color `{background}`, expected `#rrggbb`, `#rrggbbaa`, or `transparent`")
+			})
+	};
+	match hex.len() {
+		6 => Ok(Srgba::new(channel(0)?, channel(1)?, channel(2)?, 255)),
+		8 => Ok(Srgba::new(channel(0)?, channel(1)?, channel(2)?, channel(3)?)),
+		_ => Err(format!("invalid background color `{background}`, expected `#rrggbb`, `#rrggbbaa`, or `transparent`")),
+	}
+}
+
+fn parse_texture_override(texture_override: &str) -> Result<(String, String), String> {
+	match texture_override.split_once('=') {
+		Some((id, texture)) if !id.trim().is_empty() && !texture.trim().is_empty() => {
+			Ok((id.trim().trim_start_matches('#').to_string(), texture.trim().to_string()))
+		}
+		_ => Err(format!("invalid texture override `{texture_override}`, expected `id=texture`")),
+	}
+}
+
+fn parse_quarter_turn(angle: &str) -> Result<i16, String> {
+	match angle.trim().parse::<i16>() {
+		Ok(angle @ (0 | 90 | 180 | 270)) => Ok(angle),
+		_ => Err(format!("invalid rotation `{angle}`, blockstates only turn by 0, 90, 180 or 270 degrees")),
+	}
+}
+
+fn parse_vec3(vector: &str) -> Result<Vec3, String> {
+	let components = vector.split(',').map(|c| c.trim().parse::<f32>()).collect::<Result<Vec<_>, _>>();
+	match components.as_deref() {
+		Ok(&[x, y, z]) => Ok(vec3(x, y, z)),
+		_ => Err(format!("invalid vector `{vector}`, expected `x,y,z`")),
+	}
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-	run().await?;
-	Ok(())
+fn main() -> anyhow::Result<()> {
+	run()
 }
 
-async fn run() -> anyhow::Result<()> {
+fn run() -> anyhow::Result<()> {
 	let args = Args::parse();
+	anyhow::ensure!(args.fit_fraction > 0.0, "--fit-fraction must be above 0, got {}", args.fit_fraction);
+	anyhow::ensure!(args.fov > 0.0 && args.fov < 180.0, "--fov must be between 0 and 180 degrees, got {}", args.fov);
+	anyhow::ensure!(args.near > 0.0, "--near must be above 0, got {}", args.near);
+	if let Some(far) = args.far {
+		anyhow::ensure!(far > args.near, "--far must be beyond --near ({}), got {far}", args.near);
+	}
+	if let Some(ortho_scale) = args.ortho_scale {
+		anyhow::ensure!(ortho_scale > 0.0, "--ortho-scale must be above 0, got {ortho_scale}");
+	}
+	if let Some(animate_path) = &args.animate {
+		anyhow::ensure!(
+			animate_path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gif")),
+			"--animate can only write a .gif, there's no animated WebP or other encoder at hand, got {animate_path:?}"
+		);
+	}
+	anyhow::ensure!(
+		args.at.is_empty() || args.at.len() == args.json_files.len(),
+		"--at has to be given once for each model, got it {} times for {} models",
+		args.at.len(),
+		args.json_files.len()
+	);
 
-	let window = Window::new(WindowSettings {
-		title: "MC JSON Renderer".to_string(),
-		max_size: Some((args.width, args.height)),
-		..Default::default()
-	})?;
-
-	let context = window.gl();
+	let loaded = load_models(&args)?;
+	if args.list_textures {
+		for (json_file, (model, textures)) in args.json_files.iter().zip(&loaded) {
+			if args.json_files.len() > 1 {
+				println!("{json_file:?}:");
+			}
+			list_textures(model, textures.as_ref());
+		}
+		return Ok(());
+	}
 
-	let mut camera = match args.camera {
-		ArgCamera::Perspective => Camera::new_perspective(
-			window.viewport(),
+	let (eye, target, up): (Vec3, Vec3, Vec3) = match args.camera {
+		ArgCamera::Perspective | ArgCamera::Orthographic => (
 			//vec3(0.0, 0.0, 16.0 * 2.0),
 			vec3(48.0, 40.0, -32.0),
 			vec3(8.0, 8.0, 8.0),
 			vec3(0.0, 1.0, 0.0),
-			degrees(60.0),
-			0.1,
-			256.0,
-		),
-		ArgCamera::Orthographic => Camera::new_orthographic(
-			window.viewport(),
-			vec3(48.0, 40.0, -32.0),
-			vec3(8.0, 8.0, 8.0),
-			vec3(0.0, 1.0, 0.0),
-			32.0,
-			0.1,
-			256.0,
-		),
-		ArgCamera::Wiki => Camera::new_orthographic(
-			window.viewport(),
-			vec3(-32.0, 40.0, -32.0),
-			vec3(8.0, 8.0, 8.0),
-			vec3(0.0, 1.0, 0.0),
-			32.0,
-			0.1,
-			256.0,
 		),
+		ArgCamera::Wiki => (vec3(-32.0, 40.0, -32.0), vec3(8.0, 8.0, 8.0), vec3(0.0, 1.0, 0.0)),
+		ArgCamera::Inventory => (vec3(8.0, 8.0, 8.0 + 64.0), vec3(8.0, 8.0, 8.0), vec3(0.0, 1.0, 0.0)),
 	};
+	let (mut eye, mut target, up) = (args.eye.unwrap_or(eye), args.target.unwrap_or(target), args.up.unwrap_or(up));
+	anyhow::ensure!(eye != target, "the camera can't be at the point it looks at, {eye:?}");
+	anyhow::ensure!(
+		up.magnitude2() > 0.0 && up.cross(target - eye).magnitude2() > 0.0,
+		"the camera up direction {up:?} can't be zero or along the view direction"
+	);
 
-	let mc_json_model = mc_json_stuff::McModelJson::parse_json_model_from_reader(std::fs::File::open(&args.json_file)?)?;
-	let (cpu_mesh, tex_albedo) = mc_json_model.to_cpu_mesh(args.json_file.parent().expect("JSON base path must exist"))?;
-	eprintln!("The MCJson model `{:?}` has {} vertices and {} indices", args.json_file, cpu_mesh.positions.len(), cpu_mesh.indices.len().unwrap_or(0));
-	let gpu_mesh = Mesh::new(&context, &cpu_mesh);
-	// let white_cpu_texture = CpuTexture {
-	// 	name: "white".to_string(),
-	// 	data: TextureData::RU8(vec![255]),
-	// 	width: 1,
-	// 	height: 1,
-	// 	min_filter: Default::default(),
-	// 	mag_filter: Default::default(),
-	// 	mip_map_filter: None,
-	// 	wrap_s: Wrapping::Repeat,
-	// 	wrap_t: Wrapping::Repeat,
-	// };
-	let cpu_mat = CpuMaterial {
-		name: "atlas".to_string(),
-		albedo: Srgba::WHITE,
-		albedo_texture: Some(tex_albedo),
-		metallic: 0.0,
-		roughness: 0.0,
-		occlusion_metallic_roughness_texture: None,
-		metallic_roughness_texture: None,
-		occlusion_strength: 0.0,
-		occlusion_texture: None,
-		normal_scale: 0.0,
-		normal_texture: None,
-		emissive: Default::default(),
-		emissive_texture: None,
-		alpha_cutout: None,
-		lighting_model: LightingModel::Phong,
-		index_of_refraction: 0.0,
-		transmission: 0.0,
-		transmission_texture: None,
+	let event_loop = winit::event_loop::EventLoop::new();
+	let size = winit::dpi::LogicalSize::new(args.width as f64, args.height as f64);
+	let window = winit::window::WindowBuilder::new()
+		.with_inner_size(size)
+		.with_max_inner_size(size)
+		.build(&event_loop)?;
+	window.focus_window();
+	let gl = WindowedContext::from_winit_window(&window, SurfaceSettings::default()).or_else(|_| {
+		WindowedContext::from_winit_window(&window, SurfaceSettings { multisamples: 0, ..Default::default() })
+	})?;
+	let context: Context = (*gl).clone();
+	let background = render::clear_state(args.background);
+	let (window_width, window_height): (u32, u32) = window.inner_size().into();
+	let window_viewport = Viewport::new_at_origo(window_width, window_height);
+
+	let mut scene = build_scene(&context, &args, loaded)?;
+	// The inventory look is the `gui` slot, the rest show the model as it is until another slot is picked in the window
+	let mut display_slot = matches!(args.camera, ArgCamera::Inventory).then_some("gui");
+	let slot_label = set_scene_display(&mut scene, display_slot);
+	window.set_title(&format!("{WINDOW_TITLE} - {slot_label}"));
+
+	let fov = degrees(args.fov);
+	let mut ortho_height = match args.camera {
+		ArgCamera::Inventory => 16.0,
+		_ => 32.0,
 	};
-	// let mut mat = PhysicalMaterial::new(&context, &cpu_mat);
-	let mat = ColorMaterial {
-		render_states: RenderStates {
-			write_mask: WriteMask::COLOR_AND_DEPTH,
-			depth_test: DepthTest::Less,
-			blend: Blend::STANDARD_TRANSPARENCY, // Careful, STANDARD_TRANSPARENCY doesn't work right on WebGL if compiling for the web
-			cull: Cull::Back,
-		},
-		..ColorMaterial::new_transparent(&context, &cpu_mat)
+	let mut far: f32 = 256.0;
+	if args.wiki_exact {
+		anyhow::ensure!(
+			!matches!(args.camera, ArgCamera::Perspective),
+			"--wiki-exact needs an orthographic --camera like wiki"
+		);
+		if !args.height.is_power_of_two() {
+			eprintln!("Wiki renders are a power of two in size, --height {} isn't", args.height);
+		}
+		// Only the block's extent along the camera's own up matters, however the view is turned
+		let block_center = vec3(8.0, 8.0, 8.0);
+		eye = block_center + (eye - target);
+		target = block_center;
+		let forward = (target - eye).normalize();
+		let view_up = forward.cross(up).cross(forward).normalize();
+		let heights = (0..8)
+			.map(|corner| vec3((corner & 1) as f32, (corner >> 1 & 1) as f32, (corner >> 2 & 1) as f32) * 16.0)
+			.map(|corner| corner.dot(view_up));
+		let (low, high) = heights.fold((f32::MAX, f32::MIN), |(low, high), height| {
+			(low.min(height), high.max(height))
+		});
+		ortho_height = high - low;
+	}
+	if args.fit {
+		let bounds = scene.iter().filter_map(|placed| {
+			let (min, max) = placed.model.bounding_box()?;
+			Some(transformed_bounds(&(placed.display_transformation(display_slot).0 * placed.placement()), min, max))
+		});
+		let bounds = bounds.reduce(|(min_a, max_a), (min_b, max_b)| {
+			(
+				vec3(min_a.x.min(min_b.x), min_a.y.min(min_b.y), min_a.z.min(min_b.z)),
+				vec3(max_a.x.max(max_b.x), max_a.y.max(max_b.y), max_a.z.max(max_b.z)),
+			)
+		});
+		if let Some((min, max)) = bounds {
+			// Fitting the bounding sphere rather than the box keeps it framed from whatever angle the camera is at
+			let center = (min + max) * 0.5;
+			let radius = ((max - min).magnitude() * 0.5).max(0.5);
+			let dir = (eye - target).normalize();
+			let distance = match args.camera {
+				ArgCamera::Perspective => radius / args.fit_fraction / (fov / 2.0).sin(),
+				_ => (eye - target).magnitude().max(radius * 2.0),
+			};
+			target = center;
+			eye = center + dir * distance;
+			ortho_height = 2.0 * radius / args.fit_fraction;
+			far = far.max(distance + radius * 2.0);
+		} else {
+			eprintln!("The models have no elements to fit the camera to");
+		}
+	}
+	let (scene_center, scene_radius) = scene_bounding_sphere(&scene, display_slot);
+	// The default eye is placed for a full block, other sizes get it moved in or out to match
+	if args.eye.is_none() && !args.fit && matches!(args.camera, ArgCamera::Perspective) {
+		let full_block_radius = 8.0 * 3f32.sqrt();
+		eye = target + (eye - target) * (scene_radius / full_block_radius);
+		far = far.max((eye - target).magnitude() + scene_radius * 2.0);
+	}
+	// What's asked for explicitly wins over what fitting worked out
+	let far = args.far.unwrap_or(far);
+	let ortho_height = args.ortho_scale.unwrap_or(ortho_height);
+	anyhow::ensure!(far > args.near, "--near ({}) must be closer than the far clipping plane at {far}", args.near);
+	let mut camera = match args.camera {
+		ArgCamera::Perspective => Camera::new_perspective(window_viewport, eye, target, up, fov, args.near, far),
+		ArgCamera::Orthographic | ArgCamera::Wiki | ArgCamera::Inventory => {
+			Camera::new_orthographic(window_viewport, eye, target, up, ortho_height, args.near, far)
+		}
 	};
-	let model = Gm::new(gpu_mesh, mat);
+
+	// Drawn before the model so its translucent faces blend over them rather than hide them
+	let helpers = args.show_axes.then(|| (Axes::new(&context, 0.1, 20.0), render::grid_object(&context, 16)));
+	let directional = DirectionalLight::new(&context, args.light_intensity, Srgba::WHITE, &args.light_direction);
+	let ambient = AmbientLight::new(&context, args.ambient_intensity, Srgba::WHITE);
+
+	if let Some(animate_path) = &args.animate {
+		let animations = scene.iter().flat_map(|placed| &placed.mesh.animations).collect::<Vec<_>>();
+		if animations.is_empty() {
+			eprintln!("The models have no animated textures, saving just the one frame");
+		}
+		let ticks = animation::common_period(animations.iter().copied()).min(MAX_ANIMATION_TICKS);
+		let keyframes = (0..ticks)
+			.filter(|&tick| tick == 0 || animations.iter().any(|animation| animation.changes_at(tick)))
+			.collect::<Vec<_>>();
+		let mut target = OffscreenTarget::new(&context, args.width * args.ssaa, args.height * args.ssaa);
+		camera.set_viewport(target.viewport());
+		let mut frames = Vec::with_capacity(keyframes.len());
+		for (idx, &tick) in keyframes.iter().enumerate() {
+			// Every animated texture gets drawn over, so the previous tick's atlas is as good a start as the first's
+			for placed in scene.iter_mut().filter(|placed| !placed.mesh.animations.is_empty()) {
+				placed.mesh.texture = placed.mesh.texture_at_tick(tick)?;
+				placed.models = gpu_models(&context, &args, &placed.mesh, placed.alpha);
+				placed.set_display(display_slot);
+			}
+			let helpers = helper_objects(&helpers).filter(|_| args.screenshot_axes);
+			let image = render_still(
+				&args,
+				&mut target,
+				&camera,
+				helpers.chain(scene_objects(&scene)),
+				&[&directional, &ambient],
+			);
+			let shown_for = keyframes.get(idx + 1).copied().unwrap_or(ticks) - tick;
+			frames.push((image, image::Delay::from_numer_denom_ms(shown_for * 1000, TICKS_PER_SECOND)));
+		}
+		match save_gif(animate_path, frames) {
+			Ok(()) => eprintln!("Saved animation to {animate_path:?}"),
+			Err(error) => eprintln!("Failed to save animation to {animate_path:?}: {error}"),
+		}
+		return Ok(());
+	}
 
 	if let Some(screenshot_path) = &args.screenshot {
-		let mut texture = Texture2D::new_empty::<[u8; 4]>(
-			&context,
-			args.width,
-			args.height,
-			Interpolation::Nearest,
-			Interpolation::Nearest,
-			None,
-			Wrapping::ClampToEdge,
-			Wrapping::ClampToEdge,
-		);
-		let mut depth_texture = DepthTexture2D::new::<f32>(
-			&context,
-			args.width,
-			args.height,
-			Wrapping::ClampToEdge,
-			Wrapping::ClampToEdge,
-		);
-		camera.set_viewport(Viewport::new_at_origo(args.width, args.height));
-		let colors = RenderTarget::new(
-			texture.as_color_target(None),
-			depth_texture.as_depth_target(),
-		)
-			// Clear color and depth of the render target
-			.clear(ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0))
-			// Render the triangle with the per vertex colors defined at construction
-			.render(&camera, &model, &[])
-			// Read out the colors from the render target
-			.read_color::<[u8; 4]>();
-		let colors = colors.into_iter().flatten().collect::<Vec<u8>>();
-		if let Err(error) = image::save_buffer(screenshot_path, &colors, args.width, args.height, image::ColorType::Rgba8) {
-			eprintln!("Failed to save screenshot to {screenshot_path:?}: {error}");
+		let mut target = OffscreenTarget::new(&context, args.width * args.ssaa, args.height * args.ssaa);
+		camera.set_viewport(target.viewport());
+		let mut render_screenshot = |camera: &Camera| {
+			let helpers = helper_objects(&helpers).filter(|_| args.screenshot_axes);
+			render_still(&args, &mut target, camera, helpers.chain(scene_objects(&scene)), &[&directional, &ambient])
+		};
+
+		let Some(frame_count) = args.turntable else {
+			save_screenshot(&args, screenshot_path, &render_screenshot(&camera));
+			return Ok(());
+		};
+		// Just orbits the camera around the Y axis through its target, reusing the same render target each frame
+		let (eye, target, up) = (*camera.position(), *camera.target(), *camera.up());
+		let frames = (0..frame_count)
+			.map(|frame| {
+				let angle = Deg(360.0 * frame as f32 / frame_count as f32);
+				let eye = target + Mat3::from_angle_y(angle) * (eye - target);
+				camera.set_view(eye, target, up);
+				render_screenshot(&camera)
+			})
+			.collect::<Vec<_>>();
+		if screenshot_path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gif")) {
+			let frames = frames.into_iter().map(|frame| (frame, image::Delay::from_numer_denom_ms(100, 1))).collect();
+			if let Err(error) = save_gif(screenshot_path, frames) {
+				eprintln!("Failed to save turntable to {screenshot_path:?}: {error}");
+			} else {
+				eprintln!("Saved turntable to {screenshot_path:?}");
+			}
 		} else {
-			eprintln!("Saved screenshot to {screenshot_path:?}");
+			for (frame, image) in frames.iter().enumerate() {
+				save_screenshot(&args, &numbered_path(screenshot_path, frame), image);
+			}
 		}
 		return Ok(());
 	}
 
-	// let light = SpotLight::new(&context, 0.8, Srgba::WHITE, &Vec3::new(16.0, 32.0, 16.0), &Vec3::new(-16.0, -32.0, -16.0), Deg(90.0), Attenuation::default());
-	// let light = AmbientLight::new(&context, 0.7, Srgba::WHITE);
-	// let ambient = AmbientLight::new(&context, 0.4, Srgba::GREEN);
-	// let light = DirectionalLight::new(&context, 2.0, Srgba::BLUE, &vec3(0.0, -1.0, -10.0));
+	// Close enough to look the models over without going into them, and far enough out to get them all in view
+	let reach = scene_radius + (*camera.target() - scene_center).magnitude();
+	let distance = (*camera.position() - *camera.target()).magnitude();
+	let mut orbit_control = OrbitControl::new(
+		*camera.target(),
+		(reach * 1.1).min(distance),
+		(reach * 8.0).max(distance),
+	);
+	let mut watcher = args.watch.then(|| FileWatcher::new(scene_watched_paths(&args, &scene)));
 
-	let mut orbit_control = OrbitControl::new(*camera.target(), 17.0, 128.0);
-
-	window.render_loop(move |mut frame_input| {
+	render_loop(window, event_loop, gl, move |window, mut frame_input| {
 		let mut redraw = frame_input.first_frame;
 		redraw |= camera.set_viewport(frame_input.viewport);
+		// 1 to 8 pick a display slot in the order vanilla lists them, 0 goes back to the model as it is
+		for event in frame_input.events.iter_mut() {
+			let three_d::Event::KeyPress { kind, handled, .. } = event else {
+				continue;
+			};
+			let keys = [
+				Key::Num0,
+				Key::Num1,
+				Key::Num2,
+				Key::Num3,
+				Key::Num4,
+				Key::Num5,
+				Key::Num6,
+				Key::Num7,
+				Key::Num8,
+			];
+			let Some(idx) = keys.iter().position(|key| key == kind) else {
+				continue;
+			};
+			*handled = true;
+			display_slot = idx.checked_sub(1).map(|slot| McModelDisplay::SLOT_NAMES[slot]);
+			let slot_label = set_scene_display(&mut scene, display_slot);
+			window.set_title(&format!("{WINDOW_TITLE} - {slot_label}"));
+			redraw = true;
+		}
+		if watcher.as_mut().is_some_and(FileWatcher::poll) {
+			match load_models(&args).and_then(|loaded| build_scene(&context, &args, loaded)) {
+				Ok(reloaded) => {
+					scene = reloaded;
+					set_scene_display(&mut scene, display_slot);
+					// A change to the textures they use changes what's worth watching
+					watcher = Some(FileWatcher::new(scene_watched_paths(&args, &scene)));
+					eprintln!("Reloaded {:?}", args.json_files);
+				}
+				Err(error) => eprintln!("Failed to reload {:?}, keeping the last models: {error:#}", args.json_files),
+			}
+			redraw = true;
+		}
 		redraw |= orbit_control.handle_events(&mut camera, &mut frame_input.events);
 		redraw |= true; // Always redraw for now
 
@@ -183,9 +543,9 @@ async fn run() -> anyhow::Result<()> {
 			let target = frame_input.screen();
 
 			target
-				.clear(ClearState::color_and_depth(0.0, 0.0, 0.0, 1.0, 1.0))
+				.clear(background)
 				.render(
-					&camera, [&model], &[],
+					&camera, helper_objects(&helpers).chain(scene_objects(&scene)), &[&directional, &ambient],
 				);
 		}
 
@@ -194,6 +554,568 @@ async fn run() -> anyhow::Result<()> {
 			..Default::default()
 		}
 	});
+}
+
+/// What three-d's `Window::render_loop` does, but with the winit window handed to the callback so the title can be
+/// changed from it
+fn render_loop(
+	window: winit::window::Window,
+	event_loop: winit::event_loop::EventLoop<()>,
+	gl: WindowedContext,
+	mut callback: impl FnMut(&winit::window::Window, FrameInput) -> FrameOutput + 'static,
+) -> ! {
+	use winit::event::{Event, WindowEvent};
+	let mut frame_input_generator = FrameInputGenerator::from_winit_window(&window);
+	event_loop.run(move |event, _, control_flow| match event {
+		Event::MainEventsCleared => window.request_redraw(),
+		Event::RedrawRequested(_) => {
+			let frame_output = callback(&window, frame_input_generator.generate(&gl));
+			if frame_output.exit {
+				control_flow.set_exit();
+				return;
+			}
+			if frame_output.swap_buffers {
+				if let Err(error) = gl.swap_buffers() {
+					eprintln!("Failed to swap buffers: {error}");
+				}
+			}
+			if frame_output.wait_next_event {
+				control_flow.set_wait();
+			} else {
+				control_flow.set_poll();
+				window.request_redraw();
+			}
+		}
+		Event::WindowEvent { ref event, .. } => {
+			frame_input_generator.handle_winit_window_event(event);
+			match event {
+				WindowEvent::Resized(physical_size) => gl.resize(*physical_size),
+				WindowEvent::ScaleFactorChanged { new_inner_size, .. } => gl.resize(**new_inner_size),
+				WindowEvent::CloseRequested => control_flow.set_exit(),
+				_ => {}
+			}
+		}
+		_ => {}
+	})
+}
+
+/// Every model in `args.json_files` with the textures to go with it, in the same order
+fn load_models(args: &Args) -> anyhow::Result<Vec<(McModelJson, Box<dyn TextureSource>)>> {
+	args.json_files
+		.iter()
+		.map(|json_file| load_model(args, json_file).with_context(|| format!("unable to load {json_file:?}")))
+		.collect()
+}
+
+/// The model at `json_file`, from the `--pack`s if any are given, and the textures to go with it, with the
+/// `--set-texture`s applied
+fn load_model(args: &Args, json_file: &Path) -> anyhow::Result<(McModelJson, Box<dyn TextureSource>)> {
+	let (model, textures): (_, Box<dyn TextureSource>) = if !args.pack.is_empty() {
+		let packs = LayeredResourcePack::new(
+			args.pack
+				.iter()
+				.map(ResourcePack::open)
+				.collect::<anyhow::Result<Vec<_>>>()?,
+		);
+		let packs = WithBuiltinTemplates(packs);
+		(packs.resolve_model(&json_file.to_string_lossy())?, Box::new(packs.0))
+	} else {
+		let mut model = McModelJson::parse_json_model_file(json_file)?;
+		// Without a pack there's nothing to find other parents in, but the common templates don't need one
+		if model.parent.as_deref().is_some_and(BuiltinTemplates::contains) {
+			model = model.resolve_parents(&BuiltinTemplates)?;
+		}
+		(model, Box::new(FsTextureSource::new(json_file.parent().expect("JSON base path must exist"))))
+	};
+	if args.set_texture.is_empty() {
+		return Ok((model, textures));
+	}
+	TextureOverrides::apply(&args.set_texture, model, textures)
+}
+
+/// Image files given with `--set-texture` in front of the model's own textures, each under a made up texture id that
+/// the overridden variable is pointed at
+struct TextureOverrides {
+	/// The source to read each file with and the id of the file in that
+	files: HashMap<String, (FsTextureSource, String)>,
+	textures: Box<dyn TextureSource>,
+}
+
+impl TextureOverrides {
+	fn apply(
+		overrides: &[(String, String)],
+		mut model: McModelJson,
+		textures: Box<dyn TextureSource>,
+	) -> anyhow::Result<(McModelJson, Box<dyn TextureSource>)> {
+		let mut files = HashMap::new();
+		for (tex_id, texture) in overrides {
+			if !model.textures.contains_key(tex_id) {
+				let defined = model.textures.keys().map(String::as_str).collect::<Vec<_>>();
+				anyhow::bail!(
+					"--set-texture: the model has no texture variable `{tex_id}` to replace, it has {defined:?}"
+				);
+			}
+			let path = Path::new(texture);
+			let value = if path.is_file() {
+				let id = format!("set_texture:{tex_id}");
+				let source = FsTextureSource {
+					base_path: path.parent().unwrap_or(Path::new("")).to_path_buf(),
+					extensions: path.extension().map(|ext| ext.to_string_lossy().into_owned()).into_iter().collect(),
+				};
+				files.insert(id.clone(), (source, path.file_stem().unwrap_or_default().to_string_lossy().into_owned()));
+				id
+			} else {
+				texture.clone()
+			};
+			model.textures.insert(tex_id.clone(), value);
+		}
+		Ok((model, Box::new(TextureOverrides { files, textures })))
+	}
+}
+
+impl TextureSource for TextureOverrides {
+	fn load(&self, id: &str) -> Option<image::RgbaImage> {
+		self.load_detailed(id).ok()
+	}
+
+	fn load_detailed(&self, id: &str) -> anyhow::Result<image::RgbaImage> {
+		match self.files.get(id) {
+			Some((source, file_id)) => source.load_detailed(file_id),
+			None => self.textures.load_detailed(id),
+		}
+	}
+
+	fn describe_location(&self, id: &str) -> Option<String> {
+		match self.files.get(id) {
+			Some((source, file_id)) => source.describe_location(file_id),
+			None => self.textures.describe_location(id),
+		}
+	}
+
+	fn load_animation(&self, id: &str) -> anyhow::Result<Option<animation::McTextureAnimation>> {
+		match self.files.get(id) {
+			Some((source, file_id)) => source.load_animation(file_id),
+			None => self.textures.load_animation(id),
+		}
+	}
+}
+
+/// Meshes the loaded models where `--at` puts them and puts them on the GPU the way the arguments ask for, reporting
+/// what went wrong with their textures
+fn build_scene(
+	context: &Context,
+	args: &Args,
+	loaded: Vec<(McModelJson, Box<dyn TextureSource>)>,
+) -> anyhow::Result<Vec<SceneModel>> {
+	let offsets = if args.at.is_empty() {
+		(0..args.json_files.len())
+			.map(|idx| vec3(idx as f32, 0.0, 0.0))
+			.collect()
+	} else {
+		args.at.clone()
+	};
+	let placements = args.json_files.iter().zip(offsets).zip(loaded).enumerate();
+	let center = vec3(8.0, 8.0, 8.0);
+	let rotation = Mat4::from_translation(center)
+		* args
+			.blockstate_rotation()
+			.transformation()?
+			.cast::<f32>()
+			.expect("f64 fits in f32")
+		* Mat4::from_translation(-center);
+	placements
+		.map(|(idx, ((json_file, offset), (model, textures)))| {
+			let atlas_path = args.dump_atlas.as_ref().map(|path| {
+				if args.json_files.len() > 1 {
+					numbered_path(path, idx)
+				} else {
+					path.clone()
+				}
+			});
+			let (mut mesh, alpha) = mesh_model(args, json_file, &model, textures.as_ref(), atlas_path.as_deref())?;
+			mesh.translate(offset);
+			let models = gpu_models(context, args, &mesh, alpha);
+			Ok(SceneModel { json_file: json_file.clone(), offset, rotation, model, mesh, alpha, models })
+		})
+		.collect()
+}
+
+/// Meshes the model the way the arguments ask for, reporting what went wrong with its textures, along with how its
+/// transparency is going to be drawn
+fn mesh_model(
+	args: &Args,
+	json_file: &Path,
+	model: &McModelJson,
+	textures: &dyn TextureSource,
+	atlas_path: Option<&Path>,
+) -> anyhow::Result<(McModelMesh, render::AlphaMode)> {
+	let mesh_options = MeshOptions {
+		// Only the inventory look cares about how the GUI would light it, and real lights replace the baked shading
+		flat_lighting: (matches!(args.camera, ArgCamera::Inventory) && model.gui_light() == McModelGuiLight::Front)
+			|| args.lighting == ArgLighting::Phong,
+		translucency_split: args.split_translucent.then_some(u8::MAX),
+		rotation: args.blockstate_rotation(),
+		..Default::default()
+	};
+	let model_mesh = model.to_cpu_mesh_with_options(textures, &mesh_options)?;
+	eprintln!(
+		"The MCJson model `{json_file:?}` has {} vertices and {} indices",
+		model_mesh.mesh.positions.len(),
+		model_mesh.mesh.indices.len().unwrap_or(0)
+	);
+	if !model_mesh.texture_errors.is_empty() {
+		eprintln!("{} texture(s) failed to load and were replaced with the fallback:", model_mesh.texture_errors.len());
+		for error in &model_mesh.texture_errors {
+			eprintln!("  {error}");
+		}
+	}
+	for warning in &model_mesh.warnings {
+		eprintln!("Warning: {warning}");
+	}
+	if let Some(atlas_path) = atlas_path {
+		match model_mesh.atlas_image().and_then(|atlas| Ok(atlas.save(atlas_path)?)) {
+			Ok(()) => eprintln!("Saved atlas to {atlas_path:?}"),
+			Err(error) => eprintln!("Failed to save atlas to {atlas_path:?}: {error}"),
+		}
+	}
+	// Blending only where it's needed, it's what gets faces drawn out of order looking wrong
+	let alpha = match args.alpha {
+		ArgAlpha::Auto => match render::AlphaMode::for_texture_alpha(model.texture_alpha_from_source(textures)) {
+			render::AlphaMode::Cutout(_) => render::AlphaMode::Cutout(args.alpha_cutout),
+			alpha => alpha,
+		},
+		ArgAlpha::Opaque => render::AlphaMode::Opaque,
+		ArgAlpha::Cutout => render::AlphaMode::Cutout(args.alpha_cutout),
+		ArgAlpha::Blend => render::AlphaMode::Blend,
+	};
+	Ok((model_mesh, alpha))
+}
+
+/// The mesh on the GPU, shaded the way `--lighting` asks for
+fn gpu_models(context: &Context, args: &Args, model_mesh: &McModelMesh, alpha: render::AlphaMode) -> Models {
+	match args.lighting {
+		ArgLighting::Baked if model_mesh.translucent_mesh.is_some() => Models {
+			baked: render::model_objects(context, model_mesh),
+			lit: Vec::new(),
+		},
+		ArgLighting::Baked => Models {
+			baked: vec![render::model_object_with_alpha(context, model_mesh, alpha)],
+			lit: Vec::new(),
+		},
+		ArgLighting::Phong => Models {
+			baked: Vec::new(),
+			lit: render::lit_model_objects(context, model_mesh, alpha),
+		},
+	}
+}
+
+/// One of the models shown, already moved to where `--at` puts it
+struct SceneModel {
+	json_file: PathBuf,
+	/// Where it's placed in blocks
+	offset: Vec3,
+	/// The `--rotate-x` and `--rotate-y` turn about the block center its mesh was built with
+	rotation: Mat4,
+	model: McModelJson,
+	mesh: McModelMesh,
+	alpha: render::AlphaMode,
+	models: Models,
+}
+
+impl SceneModel {
+	/// [`display_transformation`] around where the model is placed rather than around the origin
+	fn display_transformation(&self, slot: Option<&str>) -> (Mat4, String) {
+		let (transformation, slot_label) = display_transformation(&self.model, slot);
+		let offset = self.offset * 16.0;
+		(Mat4::from_translation(offset) * transformation * Mat4::from_translation(-offset), slot_label)
+	}
+
+	/// Where the model's own 0-16 space ends up in the scene, before any `display` slot
+	fn placement(&self) -> Mat4 {
+		Mat4::from_translation(self.offset * 16.0) * self.rotation
+	}
+
+	fn set_display(&mut self, slot: Option<&str>) -> String {
+		let (transformation, slot_label) = self.display_transformation(slot);
+		self.models.set_transformation(transformation);
+		slot_label
+	}
+}
+
+/// Puts every model in the `display` slot keyed `slot`, what the first one calls that is what goes in the title
+fn set_scene_display(scene: &mut [SceneModel], slot: Option<&str>) -> String {
+	let slot_labels = scene.iter_mut().map(|placed| placed.set_display(slot)).collect::<Vec<_>>();
+	slot_labels.into_iter().next().unwrap_or_default()
+}
+
+fn scene_objects(scene: &[SceneModel]) -> impl Iterator<Item = &dyn Object> {
+	scene.iter().flat_map(|placed| placed.models.objects())
+}
+
+/// The model drawn either way it can be shaded, only one of the two is ever filled
+struct Models {
+	baked: Vec<Gm<Mesh, render::ModelMaterial>>,
+	lit: Vec<Gm<Mesh, PhysicalMaterial>>,
+}
+
+impl Models {
+	fn set_transformation(&mut self, transformation: Mat4) {
+		self.baked.iter_mut().for_each(|model| render::set_model_transformation(model, transformation));
+		self.lit.iter_mut().for_each(|model| render::set_model_transformation(model, transformation));
+	}
+
+	fn objects(&self) -> impl Iterator<Item = &dyn Object> {
+		self.baked.iter().map(|model| model as &dyn Object).chain(self.lit.iter().map(|model| model as &dyn Object))
+	}
+}
+
+/// The files `--watch` keeps an eye on, the model's own and those of its textures, including the ones that don't
+/// exist yet under the other extensions so that adding one is noticed too.  Zipped packs are watched as a whole.
+fn watched_paths(args: &Args, json_file: &Path, model: &McModelJson) -> Vec<PathBuf> {
+	let textures = model.resolved_textures().unwrap_or_default();
+	let textures = textures.values().collect::<Vec<_>>();
+	if args.pack.is_empty() {
+		let source = FsTextureSource::new(json_file.parent().expect("JSON base path must exist"));
+		let texture_paths = textures.iter().flat_map(|id| {
+			let path = source.base_path.join(McResourceLocation::parse(id).path);
+			// Appended rather than `with_extension`, which would replace whatever follows a dot in the id itself
+			TEXTURE_EXTENSIONS.iter().map(move |ext| {
+				let mut path = path.clone().into_os_string();
+				path.push(format!(".{ext}"));
+				PathBuf::from(path)
+			})
+		});
+		return std::iter::once(json_file.to_path_buf()).chain(texture_paths).collect();
+	}
+	let mut paths = Vec::new();
+	for pack in &args.pack {
+		if !pack.is_dir() {
+			paths.push(pack.clone());
+			continue;
+		}
+		paths.push(pack.join(ResourcePack::model_path(&json_file.to_string_lossy())));
+		for id in &textures {
+			let stem = ResourcePack::texture_path_stem(id);
+			paths.extend(TEXTURE_EXTENSIONS.iter().map(|ext| pack.join(format!("{stem}.{ext}"))));
+		}
+	}
+	paths
+}
+
+/// [`watched_paths`] of every model in the scene
+fn scene_watched_paths(args: &Args, scene: &[SceneModel]) -> Vec<PathBuf> {
+	let overrides = args.set_texture.iter().map(|(_, texture)| PathBuf::from(texture)).filter(|path| path.is_file());
+	scene.iter().flat_map(|placed| watched_paths(args, &placed.json_file, &placed.model)).chain(overrides).collect()
+}
+
+/// Notices files changing by polling when they were last modified, which is all it takes to catch an editor saving
+struct FileWatcher {
+	files: Vec<(PathBuf, Option<SystemTime>)>,
+	last_poll: Instant,
+}
+
+impl FileWatcher {
+	const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+	fn new(paths: Vec<PathBuf>) -> FileWatcher {
+		let files = paths.into_iter().map(|path| {
+			let modified = Self::modified(&path);
+			(path, modified)
+		});
+		FileWatcher { files: files.collect(), last_poll: Instant::now() }
+	}
+
+	fn modified(path: &Path) -> Option<SystemTime> {
+		std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+	}
+
+	/// Whether any of the files were changed, created or removed since the last time this said so, checking at most
+	/// every [`Self::POLL_INTERVAL`]
+	fn poll(&mut self) -> bool {
+		if self.last_poll.elapsed() < Self::POLL_INTERVAL {
+			return false;
+		}
+		self.last_poll = Instant::now();
+		let mut changed = false;
+		for (path, modified) in &mut self.files {
+			let now = Self::modified(path);
+			if now != *modified {
+				*modified = now;
+				changed = true;
+			}
+		}
+		changed
+	}
+}
+
+/// The `--show-axes` axes and grid, if shown
+fn helper_objects(helpers: &Option<(Axes, Gm<InstancedMesh, ColorMaterial>)>) -> impl Iterator<Item = &dyn Object> {
+	helpers.iter().flat_map(|(axes, grid)| [axes as &dyn Object, grid as &dyn Object])
+}
+
+/// Where the model goes in the `display` slot keyed `slot`, or for `None` the model as it is, along with what to call
+/// that in the window title
+fn display_transformation(model: &McModelJson, slot: Option<&str>) -> (Mat4, String) {
+	let Some(slot) = slot else {
+		return (Mat4::identity(), "raw".to_string());
+	};
+	match model.display.as_ref().and_then(|display| display.slot(slot)) {
+		Some(transform) => (transform.to_matrix(), slot.to_string()),
+		// Item models without a `gui` slot of their own would be drawn flat facing the camera, blocks get tilted
+		None if slot == "gui" && !model.elements.is_empty() => (
+			McModelTransform::default_block_gui().to_matrix(),
+			format!("{slot} (block default)"),
+		),
+		None => (Mat4::identity(), format!("{slot} (not set)")),
+	}
+}
+
+fn list_textures(model: &McModelJson, textures: &dyn TextureSource) {
+	if let Some(parent) = model
+		.parent
+		.as_ref()
+		.filter(|parent| !McResourceLocation::parse(parent).path.starts_with("builtin/"))
+	{
+		println!("Parent `{parent}` isn't followed without a pack to find it in, see `--pack`");
+	}
+	for (tex_id, value) in &model.textures {
+		let texture = match model.resolve_texture(tex_id) {
+			Ok(Some(texture)) => texture,
+			Ok(None) => {
+				println!("#{tex_id} = {value}: refers to an undefined variable");
+				continue;
+			}
+			Err(error) => {
+				println!("#{tex_id} = {value}: {error}");
+				continue;
+			}
+		};
+		println!("#{tex_id} = {value} -> {texture}");
+		let location = textures.describe_location(texture).unwrap_or_else(|| "unknown location".to_string());
+		match textures.load_detailed(texture) {
+			Ok(image) => println!("    {location}: loaded, {}x{}", image.width(), image.height()),
+			Err(error) => println!("    {location}: failed, {error:#}"),
+		}
+	}
+}
+
+/// Axis aligned bounds of a box after going through a transformation
+fn transformed_bounds(transformation: &Mat4, min: Vec3, max: Vec3) -> (Vec3, Vec3) {
+	let corners = (0..8).map(|corner| {
+		let pick = |axis: usize| if corner & (1 << axis) == 0 { min[axis] } else { max[axis] };
+		(transformation * vec4(pick(0), pick(1), pick(2), 1.0)).truncate()
+	});
+	corners.fold(
+		(vec3(f32::MAX, f32::MAX, f32::MAX), vec3(f32::MIN, f32::MIN, f32::MIN)),
+		|(min, max), pos| {
+			(
+				vec3(min.x.min(pos.x), min.y.min(pos.y), min.z.min(pos.z)),
+				vec3(max.x.max(pos.x), max.y.max(pos.y), max.z.max(pos.z)),
+			)
+		},
+	)
+}
+
+/// A sphere around every model of the scene where they're placed and shown in the `display` slot keyed `slot`, going
+/// by each model's own bounding sphere
+fn scene_bounding_sphere(scene: &[SceneModel], slot: Option<&str>) -> (Vec3, f32) {
+	let spheres = scene.iter().map(|placed| {
+		let (center, radius) = placed.model.bounding_sphere();
+		let transformation = placed.display_transformation(slot).0 * placed.placement();
+		let scale = [transformation.x, transformation.y, transformation.z]
+			.map(|axis| axis.truncate().magnitude())
+			.into_iter()
+			.fold(0.0, f32::max);
+		((transformation * center.extend(1.0)).truncate(), radius * scale)
+	});
+	spheres
+		.reduce(|(center_a, radius_a), (center_b, radius_b)| {
+			let apart = (center_b - center_a).magnitude();
+			if apart + radius_b <= radius_a {
+				(center_a, radius_a)
+			} else if apart + radius_a <= radius_b {
+				(center_b, radius_b)
+			} else {
+				let radius = (apart + radius_a + radius_b) * 0.5;
+				(center_a + (center_b - center_a) * ((radius - radius_a) / apart), radius)
+			}
+		})
+		.unwrap_or_else(|| McModelJson::default().bounding_sphere())
+}
+
+/// `path` with `_000` and so on after its name, going by `.png` if it has no extension
+fn numbered_path(path: &Path, number: usize) -> PathBuf {
+	let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+	let extension = path.extension().map_or("png".into(), |ext| ext.to_string_lossy());
+	path.with_file_name(format!("{stem}_{number:03}.{extension}"))
+}
+
+fn save_screenshot(args: &Args, screenshot_path: &Path, image: &image::RgbaImage) {
+	if let Err(error) = write_image(args, screenshot_path, image) {
+		eprintln!("Failed to save screenshot to {screenshot_path:?}: {error}");
+	} else {
+		eprintln!("Saved screenshot to {screenshot_path:?}");
+	}
+}
+
+/// Encodes as `--format` says, or by the extension without one
+fn write_image(args: &Args, path: &Path, image: &image::RgbaImage) -> anyhow::Result<()> {
+	let Some(format) = args.format else {
+		return Ok(image.save(path)?);
+	};
+	let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+	match format {
+		ArgFormat::Png => image.write_to(&mut writer, image::ImageFormat::Png)?,
+		ArgFormat::Jpeg => {
+			// A transparent background would come out black, white is what something without one is usually shown on
+			let background = if args.background.a == 0 {
+				[255, 255, 255]
+			} else {
+				[args.background.r, args.background.g, args.background.b]
+			};
+			if image.pixels().any(|pixel| pixel.0[3] < u8::MAX) {
+				eprintln!("JPEG can't keep transparency, flattening the screenshot onto {background:?}");
+			}
+			let flattened = image::RgbImage::from_fn(image.width(), image.height(), |x, y| {
+				let [r, g, b, a] = image.get_pixel(x, y).0;
+				let alpha = a as f32 / 255.0;
+				image::Rgb(
+					[0, 1, 2]
+						.map(|c| ([r, g, b][c] as f32 * alpha + background[c] as f32 * (1.0 - alpha)).round() as u8),
+				)
+			});
+			image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, args.quality).encode_image(&flattened)?;
+		}
+		ArgFormat::Webp => image::codecs::webp::WebPEncoder::new_lossless(&mut writer).encode(
+			image.as_raw(),
+			image.width(),
+			image.height(),
+			image::ExtendedColorType::Rgba8,
+		)?,
+	}
+	Ok(())
+}
+
+/// Renders what the screenshot options ask for, downscaling the supersampled image back down to the asked for size
+fn render_still<'a>(
+	args: &Args,
+	target: &mut OffscreenTarget,
+	camera: &Camera,
+	objects: impl IntoIterator<Item = &'a dyn Object>,
+	lights: &[&dyn Light],
+) -> image::RgbaImage {
+	let image = target.render(camera, objects, lights, args.background);
+	if args.ssaa > 1 {
+		image::imageops::resize(&image, args.width, args.height, image::imageops::FilterType::Lanczos3)
+	} else {
+		image
+	}
+}
 
+fn save_gif(path: &Path, frames: Vec<(image::RgbaImage, image::Delay)>) -> anyhow::Result<()> {
+	use image::codecs::gif::{GifEncoder, Repeat};
+	let mut encoder = GifEncoder::new(std::io::BufWriter::new(std::fs::File::create(path)?));
+	encoder.set_repeat(Repeat::Infinite)?;
+	encoder.encode_frames(frames.into_iter().map(|(frame, delay)| image::Frame::from_parts(frame, 0, 0, delay)))?;
 	Ok(())
 }
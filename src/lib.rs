@@ -1,13 +1,26 @@
 use std::collections::HashMap;
 use std::hash::Hash;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use anyhow::Context as AnyContext;
 use image::{GenericImage, RgbaImage};
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 use three_d::*;
 
+use crate::animation::{AtlasAnimation, McTextureAnimation};
+
+pub mod animation;
+pub mod lint;
+pub mod outline;
+pub mod pack;
+pub mod raster;
+#[cfg(feature = "headless")]
+pub mod render;
+mod strict;
+pub mod templates;
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum McModelRotationAxis {
@@ -16,7 +29,7 @@ pub enum McModelRotationAxis {
 	Z,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum McModelDirection {
 	North,
@@ -28,6 +41,18 @@ pub enum McModelDirection {
 }
 
 impl McModelDirection {
+	/// The name it has in model json, like `north`
+	pub fn name(&self) -> &'static str {
+		match self {
+			McModelDirection::North => "north",
+			McModelDirection::East => "east",
+			McModelDirection::South => "south",
+			McModelDirection::West => "west",
+			McModelDirection::Up => "up",
+			McModelDirection::Down => "down",
+		}
+	}
+
 	pub fn get_normal(&self) -> Vec3 {
 		match self {
 			McModelDirection::North => vec3(0.0, 0.0, -1.0),
@@ -57,19 +82,109 @@ impl McModelDirection {
 		Srgba::from([shading, shading, shading, 1.0])
 	}
 
-	// pub fn get_next_dir_around(self, axis: McModelRotationAxis) -> McModelDirection {
-	// 	use McModelRotationAxis::*;
-	// 	use McModelDirection::*;
-	// 	match (axis, self) {
-	// 		(X, North) => Down,
-	// 		(X, South) => Up,
-	// 		(X, East) => East,
-	// 		(X, West) => West,
-	// 		(X, Up) => North,
-	// 		(X, Down) => South,
-	//       ...
-	// 	}
-	// }
+	/// The texture `u` (rightwards) and `v` (downwards) axes of this face as seen from outside the block
+	fn get_uv_axes(&self) -> (Vector3<f64>, Vector3<f64>) {
+		match self {
+			McModelDirection::North => (vec3(-1.0, 0.0, 0.0), vec3(0.0, -1.0, 0.0)),
+			McModelDirection::East => (vec3(0.0, 0.0, -1.0), vec3(0.0, -1.0, 0.0)),
+			McModelDirection::South => (vec3(1.0, 0.0, 0.0), vec3(0.0, -1.0, 0.0)),
+			McModelDirection::West => (vec3(0.0, 0.0, 1.0), vec3(0.0, -1.0, 0.0)),
+			McModelDirection::Up => (vec3(1.0, 0.0, 0.0), vec3(0.0, 0.0, 1.0)),
+			McModelDirection::Down => (vec3(1.0, 0.0, 0.0), vec3(0.0, 0.0, -1.0)),
+		}
+	}
+
+	/// The corners of this side of the `p0`..`p1` box, counter-clockwise as seen from outside and starting at the
+	/// corner the face's `u1`, `v0` uv lands on when it's not rotated
+	fn quad_corners(&self, p0: Vector3<f64>, p1: Vector3<f64>) -> [Vector3<f64>; 4] {
+		match self {
+			McModelDirection::North => [
+				vec3(p0.x, p0.y, p0.z),
+				vec3(p0.x, p1.y, p0.z),
+				vec3(p1.x, p1.y, p0.z),
+				vec3(p1.x, p0.y, p0.z),
+			],
+			McModelDirection::East => [
+				vec3(p1.x, p0.y, p0.z),
+				vec3(p1.x, p1.y, p0.z),
+				vec3(p1.x, p1.y, p1.z),
+				vec3(p1.x, p0.y, p1.z),
+			],
+			McModelDirection::South => [
+				vec3(p1.x, p0.y, p1.z),
+				vec3(p1.x, p1.y, p1.z),
+				vec3(p0.x, p1.y, p1.z),
+				vec3(p0.x, p0.y, p1.z),
+			],
+			McModelDirection::West => [
+				vec3(p0.x, p0.y, p1.z),
+				vec3(p0.x, p1.y, p1.z),
+				vec3(p0.x, p1.y, p0.z),
+				vec3(p0.x, p0.y, p0.z),
+			],
+			McModelDirection::Up => [
+				vec3(p1.x, p1.y, p1.z),
+				vec3(p1.x, p1.y, p0.z),
+				vec3(p0.x, p1.y, p0.z),
+				vec3(p0.x, p1.y, p1.z),
+			],
+			McModelDirection::Down => [
+				vec3(p1.x, p0.y, p0.z),
+				vec3(p1.x, p0.y, p1.z),
+				vec3(p0.x, p0.y, p1.z),
+				vec3(p0.x, p0.y, p0.z),
+			],
+		}
+	}
+
+	/// Snaps a (rotated) normal back onto one of the six directions
+	fn from_normal_f64(normal: Vector3<f64>) -> McModelDirection {
+		let max = normal.x.abs().max(normal.y.abs()).max(normal.z.abs());
+		if normal.x == max {
+			McModelDirection::East
+		} else if -normal.x == max {
+			McModelDirection::West
+		} else if normal.y == max {
+			McModelDirection::Up
+		} else if -normal.y == max {
+			McModelDirection::Down
+		} else if normal.z == max {
+			McModelDirection::South
+		} else {
+			McModelDirection::North
+		}
+	}
+
+	/// The direction nearest to `normal`, `None` if it's zero or not finite so it doesn't point anywhere
+	pub fn from_normal(normal: Vec3) -> Option<McModelDirection> {
+		let normal = normal.cast::<f64>()?;
+		(normal.x.is_finite() && normal.y.is_finite() && normal.z.is_finite() && normal.magnitude2() > 0.0)
+			.then(|| McModelDirection::from_normal_f64(normal))
+	}
+
+	/// This direction turned by 90 degree steps around `axis`, counter-clockwise as seen looking back from the axis'
+	/// positive end (so a quarter turn around Y takes east to north), negative turns going the other way.  Blockstate
+	/// rotations turn clockwise instead, a `y` of 90 being `-1` here.
+	pub fn rotate(self, axis: McModelRotationAxis, quarter_turns: i32) -> McModelDirection {
+		use McModelDirection::*;
+		let quarter_turn = |dir: McModelDirection| match (axis, dir) {
+			(McModelRotationAxis::X, Up) => South,
+			(McModelRotationAxis::X, South) => Down,
+			(McModelRotationAxis::X, Down) => North,
+			(McModelRotationAxis::X, North) => Up,
+			(McModelRotationAxis::Y, East) => North,
+			(McModelRotationAxis::Y, North) => West,
+			(McModelRotationAxis::Y, West) => South,
+			(McModelRotationAxis::Y, South) => East,
+			(McModelRotationAxis::Z, East) => Up,
+			(McModelRotationAxis::Z, Up) => West,
+			(McModelRotationAxis::Z, West) => Down,
+			(McModelRotationAxis::Z, Down) => East,
+			// Those along the axis itself stay where they are
+			(_, dir) => dir,
+		};
+		(0..quarter_turns.rem_euclid(4)).fold(self, |dir, _| quarter_turn(dir))
+	}
 
 	// pub fn get_shading_from_face(&self, _rot: &McModelRotation) -> f32 {
 	// 	// use McModelRotationAxis::*;
@@ -99,18 +214,68 @@ pub struct McModelRotation {
 	pub angle: f64,
 	pub axis: McModelRotationAxis,
 	pub origin: [f64; 3],
+	/// Stretch the faces across the whole block again after rotating, only on the axes being rotated
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub rescale: bool,
+	/// Keys this crate doesn't understand, like the `scale`/`offset` some modded formats add, kept so they survive
+	/// being written back out rather than failing the parse
+	#[serde(flatten)]
+	pub extra: IndexMap<String, serde_json::Value>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct McModelFace {
-	pub uv: [f64; 4],
+	/// Left out it gets worked out from where the element sits, see `McModelElement::face_uv`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub uv: Option<[f64; 4]>,
 	pub texture: String,
 	#[serde(default, skip_serializing_if = "num_traits::identities::Zero::is_zero")]
 	pub rotation: i16,
 	pub cullface: Option<McModelDirection>,
+	/// Which of the block's tint colors the face gets multiplied with, like grass and leaves, the index only means
+	/// something to the block being drawn so here it's only ever whether there's one
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub tintindex: Option<i32>,
+	/// Draw the face fullbright, without the directional shading or ambient occlusion, like something glowing.  That's
+	/// only as bright as the texture itself though, actual light emission would need a material that does HDR.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub emissive: Option<bool>,
+	/// Keep the texture lined up with the block's axes when the element's own `rotation` spins this face around in
+	/// place, instead of turning along with it.  A blockstate's `uvlock` is about the whole model turning in quarter
+	/// turns, this is about the element's rotation at any angle, so the texture gets scaled up to still cover the face.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub uvlock: bool,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl McModelRotation {
+	/// Rotation by `angle` degrees around `axis` through `origin`, without rescaling
+	pub fn new(axis: McModelRotationAxis, angle: f64, origin: [f64; 3]) -> McModelRotation {
+		McModelRotation {
+			angle,
+			axis,
+			origin,
+			rescale: false,
+			extra: IndexMap::new(),
+		}
+	}
+}
+
+impl McModelFace {
+	/// Face showing `texture`, a `#variable`, with the vanilla automatic uvs and nothing else set
+	pub fn new(texture: impl Into<String>) -> McModelFace {
+		McModelFace {
+			uv: None,
+			texture: texture.into(),
+			rotation: 0,
+			cullface: None,
+			tintindex: None,
+			emissive: None,
+			uvlock: false,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct McModelFaces {
 	pub north: Option<McModelFace>,
 	pub east: Option<McModelFace>,
@@ -120,357 +285,3007 @@ pub struct McModelFaces {
 	pub down: Option<McModelFace>,
 }
 
+impl McModelFaces {
+	/// The faces that are there along with their side, in north, east, south, west, up, down order
+	pub fn iter(&self) -> impl Iterator<Item = (McModelDirection, &McModelFace)> {
+		[
+			(McModelDirection::North, &self.north),
+			(McModelDirection::East, &self.east),
+			(McModelDirection::South, &self.south),
+			(McModelDirection::West, &self.west),
+			(McModelDirection::Up, &self.up),
+			(McModelDirection::Down, &self.down),
+		]
+		.into_iter()
+		.filter_map(|(dir, face)| Some((dir, face.as_ref()?)))
+	}
+
+	/// Same as `iter` but mutable
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = (McModelDirection, &mut McModelFace)> {
+		[
+			(McModelDirection::North, &mut self.north),
+			(McModelDirection::East, &mut self.east),
+			(McModelDirection::South, &mut self.south),
+			(McModelDirection::West, &mut self.west),
+			(McModelDirection::Up, &mut self.up),
+			(McModelDirection::Down, &mut self.down),
+		]
+		.into_iter()
+		.filter_map(|(dir, face)| Some((dir, face.as_mut()?)))
+	}
+
+	/// Puts `face` on the `dir` side, replacing whatever was there
+	pub fn set(&mut self, dir: McModelDirection, face: McModelFace) {
+		let slot = match dir {
+			McModelDirection::North => &mut self.north,
+			McModelDirection::East => &mut self.east,
+			McModelDirection::South => &mut self.south,
+			McModelDirection::West => &mut self.west,
+			McModelDirection::Up => &mut self.up,
+			McModelDirection::Down => &mut self.down,
+		};
+		*slot = Some(face);
+	}
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct McModelElement {
+	/// What Blockbench calls the element in its outliner, vanilla ignores it
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
 	pub from: [f64; 3],
 	pub to: [f64; 3],
 	pub faces: McModelFaces,
 	pub rotation: Option<McModelRotation>,
+	/// Whether the faces get the per-direction shading, off for things like the crossed planes of plants that would
+	/// otherwise come out darker from some sides than others
+	#[serde(default = "McModelElement::default_shade", skip_serializing_if = "McModelElement::is_default_shade")]
+	pub shade: bool,
+}
+
+impl Default for McModelElement {
+	fn default() -> Self {
+		McModelElement {
+			name: None,
+			from: [0.0; 3],
+			to: [0.0; 3],
+			faces: McModelFaces::default(),
+			rotation: None,
+			shade: Self::default_shade(),
+		}
+	}
 }
 
+/// What the game clamps `display` translations to, in 1/16th of a block, five blocks either way
+pub const DISPLAY_TRANSLATION_RANGE: std::ops::RangeInclusive<f64> = -80.0..=80.0;
+
+/// What the game clamps `display` scales to.  The bottom is -4 rather than 0 because the game's own clamp keeps
+/// negative scales, which mirror the model, so clamping them to 0 would flatten models the game draws mirrored.
+pub const DISPLAY_SCALE_RANGE: std::ops::RangeInclusive<f64> = -4.0..=4.0;
+
+/// How a model gets placed in one of the `display` contexts
 #[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct McModelTransform {
+	#[serde(default)]
+	pub rotation: [f64; 3],
+	#[serde(default)]
+	pub translation: [f64; 3],
+	#[serde(default = "McModelTransform::default_scale")]
+	pub scale: [f64; 3],
+}
+
+impl Default for McModelTransform {
+	fn default() -> Self {
+		McModelTransform {
+			rotation: [0.0; 3],
+			translation: [0.0; 3],
+			scale: Self::default_scale(),
+		}
+	}
+}
+
+impl McModelTransform {
+	fn default_scale() -> [f64; 3] {
+		[1.0; 3]
+	}
+
+	/// What vanilla's `block/block` uses for inventory icons
+	pub fn default_block_gui() -> McModelTransform {
+		McModelTransform {
+			rotation: [30.0, 225.0, 0.0],
+			translation: [0.0; 3],
+			scale: [0.625; 3],
+		}
+	}
+
+	/// Transformation about the block center in model space the way the game applies it: scaled first, then rotated
+	/// by Z, Y and X in that order (the json's X, Y, Z euler angles), then translated.  Translation is in 1/16th of a
+	/// block like the rest of the model while scale is a plain factor, and both get clamped to
+	/// [`DISPLAY_TRANSLATION_RANGE`] and [`DISPLAY_SCALE_RANGE`] like the game does when it loads them.
+	pub fn to_matrix(&self) -> Mat4 {
+		let [rx, ry, rz] = self.rotation.map(|r| r as f32);
+		let [tx, ty, tz] = self
+			.translation
+			.map(|t| t.clamp(*DISPLAY_TRANSLATION_RANGE.start(), *DISPLAY_TRANSLATION_RANGE.end()) as f32);
+		let [sx, sy, sz] = self
+			.scale
+			.map(|s| s.clamp(*DISPLAY_SCALE_RANGE.start(), *DISPLAY_SCALE_RANGE.end()) as f32);
+		let center = vec3(8.0, 8.0, 8.0);
+		Mat4::from_translation(center + vec3(tx, ty, tz))
+			* Mat4::from_angle_x(Deg(rx))
+			* Mat4::from_angle_y(Deg(ry))
+			* Mat4::from_angle_z(Deg(rz))
+			* Mat4::from_nonuniform_scale(sx, sy, sz)
+			* Mat4::from_translation(-center)
+	}
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct McModelDisplay {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub thirdperson_righthand: Option<McModelTransform>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub thirdperson_lefthand: Option<McModelTransform>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub firstperson_righthand: Option<McModelTransform>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub firstperson_lefthand: Option<McModelTransform>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub gui: Option<McModelTransform>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub head: Option<McModelTransform>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub ground: Option<McModelTransform>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub fixed: Option<McModelTransform>,
+}
+
+impl McModelDisplay {
+	/// The slot names as they're keyed in the json, in the order vanilla lists them
+	pub const SLOT_NAMES: [&'static str; 8] = [
+		"thirdperson_righthand",
+		"thirdperson_lefthand",
+		"firstperson_righthand",
+		"firstperson_lefthand",
+		"gui",
+		"head",
+		"ground",
+		"fixed",
+	];
+
+	/// The transform of the slot keyed `name` in the json, if it's set
+	pub fn slot(&self, name: &str) -> Option<&McModelTransform> {
+		match name {
+			"thirdperson_righthand" => self.thirdperson_righthand.as_ref(),
+			"thirdperson_lefthand" => self.thirdperson_lefthand.as_ref(),
+			"firstperson_righthand" => self.firstperson_righthand.as_ref(),
+			"firstperson_lefthand" => self.firstperson_lefthand.as_ref(),
+			"gui" => self.gui.as_ref(),
+			"head" => self.head.as_ref(),
+			"ground" => self.ground.as_ref(),
+			"fixed" => self.fixed.as_ref(),
+			_ => None,
+		}
+	}
+
+	/// Fills in whichever slots this one doesn't define from the parent's
+	fn merge_parent(&mut self, parent: McModelDisplay) {
+		self.thirdperson_righthand = self.thirdperson_righthand.take().or(parent.thirdperson_righthand);
+		self.thirdperson_lefthand = self.thirdperson_lefthand.take().or(parent.thirdperson_lefthand);
+		self.firstperson_righthand = self.firstperson_righthand.take().or(parent.firstperson_righthand);
+		self.firstperson_lefthand = self.firstperson_lefthand.take().or(parent.firstperson_lefthand);
+		self.gui = self.gui.take().or(parent.gui);
+		self.head = self.head.take().or(parent.head);
+		self.ground = self.ground.take().or(parent.ground);
+		self.fixed = self.fixed.take().or(parent.fixed);
+	}
+}
+
+/// How a model is lit when drawn in the GUI
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
-pub enum McModelDisplay {
-	FirstpersonRighthand {
-		rotation: [f64; 3],
-		translation: [f64; 3],
-		scale: [f64; 3],
-	},
+pub enum McModelGuiLight {
+	/// Lit straight on, for flat items
+	Front,
+	/// Lit from the side like a block in the world
+	#[default]
+	Side,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// The texture variable for the model's particles, which no face needs to reference
+pub const PARTICLE_TEXTURE: &str = "particle";
+
+/// The parent of `item/generated`, a parent chain ending at it means the model is built from its `layer0`..`layerN`
+/// textures like `McModelJson::to_item_mesh` does rather than from elements
+pub const BUILTIN_GENERATED: &str = "builtin/generated";
+
+/// The parent of models the game draws in code of its own, like chests, signs and shulker boxes as items, see
+/// [`McModelError::BuiltinEntityModel`]
+pub const BUILTIN_ENTITY: &str = "builtin/entity";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct McModelJson {
-	pub parent: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub parent: Option<String>,
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub display: Option<McModelDisplay>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub gui_light: Option<McModelGuiLight>,
+	/// Whether the game smooth lights the model, inherited from the parent when left out and defaulting to on, see
+	/// `McModelJson::ambient_occlusion`
+	#[serde(rename = "ambientocclusion", default, skip_serializing_if = "Option::is_none")]
+	pub ambient_occlusion: Option<bool>,
+	#[serde(default)]
 	pub textures: IndexMap<String, String>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	pub elements: Vec<McModelElement>,
+	/// Item models swapped in by the item's properties, see `McModelJson::select_override`.  Unlike everything else
+	/// these aren't inherited from the parent.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub overrides: Vec<McModelOverride>,
+	/// Keys this crate doesn't model, like the `loader` and `render_type` of modded models, kept so that a model that's
+	/// read in and written back out doesn't lose them
+	#[serde(flatten)]
+	pub extra: IndexMap<String, serde_json::Value>,
 }
 
+/// An item model `overrides` entry, using `model` instead when every predicate holds
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct McModelOverride {
+	/// Item property, like `custom_model_data` or `damage`, to the least value it needs to have
+	pub predicate: IndexMap<String, f64>,
+	pub model: String,
+}
 
-impl McModelElement {
-	pub fn faces_enabled(&self) -> usize {
-		let mut count = 0;
-		if self.faces.north.is_some() {
-			count += 1;
+/// Puts a `McModelJson` together in code for procedural and test models, like a slab with
+/// `McModelBuilder::new().texture("all", "block/stone").element([0.0; 3], [16.0, 8.0, 16.0])` followed by a
+/// `.face(McModelDirection::Up, "#all", None)` for each side and then `.build()`
+#[derive(Clone, Debug, Default)]
+pub struct McModelBuilder {
+	model: McModelJson,
+}
+
+impl McModelBuilder {
+	pub fn new() -> McModelBuilder {
+		McModelBuilder::default()
+	}
+
+	pub fn parent(mut self, parent: impl Into<String>) -> McModelBuilder {
+		self.model.parent = Some(parent.into());
+		self
+	}
+
+	pub fn gui_light(mut self, gui_light: McModelGuiLight) -> McModelBuilder {
+		self.model.gui_light = Some(gui_light);
+		self
+	}
+
+	pub fn ambient_occlusion(mut self, ambient_occlusion: bool) -> McModelBuilder {
+		self.model.ambient_occlusion = Some(ambient_occlusion);
+		self
+	}
+
+	/// Sets the texture variable `id` (without the `#`) to `path`, which can also be a `#other` reference
+	pub fn texture(mut self, id: impl Into<String>, path: impl Into<String>) -> McModelBuilder {
+		self.model.textures.insert(id.into(), path.into());
+		self
+	}
+
+	/// Starts a new element, the `face` and `rotation` calls after it apply to this one
+	pub fn element(mut self, from: [f64; 3], to: [f64; 3]) -> McModelBuilder {
+		self.model.elements.push(McModelElement::new(from, to));
+		self
+	}
+
+	/// Gives the current element a face on the `dir` side, `texture` being a `#variable`, with `uv` left as `None`
+	/// to get the vanilla automatic uvs
+	///
+	/// # Panics
+	/// When there's no element yet to put it on.
+	pub fn face(
+		mut self,
+		dir: McModelDirection,
+		texture: impl Into<String>,
+		uv: impl Into<Option<[f64; 4]>>,
+	) -> McModelBuilder {
+		let face = McModelFace {
+			uv: uv.into(),
+			..McModelFace::new(texture)
+		};
+		self.current_element().faces.set(dir, face);
+		self
+	}
+
+	/// Turns the per-direction shading of the current element's faces on or off
+	///
+	/// # Panics
+	/// When there's no element yet.
+	pub fn shade(mut self, shade: bool) -> McModelBuilder {
+		self.current_element().shade = shade;
+		self
+	}
+
+	/// Rotates the current element by `angle` degrees around `axis` through `origin`
+	///
+	/// # Panics
+	/// When there's no element yet to rotate.
+	pub fn rotation(mut self, axis: McModelRotationAxis, angle: f64, origin: [f64; 3]) -> McModelBuilder {
+		self.current_element().rotation = Some(McModelRotation::new(axis, angle, origin));
+		self
+	}
+
+	pub fn build(self) -> McModelJson {
+		self.model
+	}
+
+	fn current_element(&mut self) -> &mut McModelElement {
+		self.model.elements.last_mut().expect("`element` has to be called before adding faces or rotations to it")
+	}
+}
+
+/// A built model mesh along with its atlas texture
+#[derive(Clone, Debug)]
+pub struct McModelMesh {
+	/// Every face, or with [`MeshOptions::translucency_split`] only the opaque ones
+	pub mesh: CpuMesh,
+	/// The faces with translucent texels when built with [`MeshOptions::translucency_split`], it shares `texture`
+	pub translucent_mesh: Option<CpuMesh>,
+	pub texture: CpuTexture,
+	/// Texture ID to the `[x, y, width, height]` rectangle it was packed into on the atlas, textures that failed to
+	/// load aren't in here as they use the not-found checkerboard instead
+	pub atlas_mappings: IndexMap<String, [u32; 4]>,
+	/// Textures that couldn't be loaded and were drawn with the fallback texture instead, along with faces whose
+	/// texture doesn't get to any texture at all, like a `#variable` nothing defines
+	pub texture_errors: Vec<TextureLoadError>,
+	/// Things about the model that got adjusted to be able to build it, like clamped coordinates
+	pub warnings: Vec<String>,
+	/// Textures with an animation in their `.mcmeta`, `texture` has them at their first frame
+	pub animations: Vec<AtlasAnimation>,
+}
+
+impl McModelMesh {
+	/// The packed atlas as an image at the size it was allocated at, to look over what got packed where
+	pub fn atlas_image(&self) -> anyhow::Result<RgbaImage> {
+		let TextureData::RgbaU8(texels) = &self.texture.data else {
+			anyhow::bail!("atlas texture isn't RgbaU8");
+		};
+		let raw = texels.iter().flatten().copied().collect();
+		RgbaImage::from_raw(self.texture.width, self.texture.height, raw)
+			.context("atlas texture data doesn't match its size")
+	}
+
+	/// Ticks until every animated texture is back at its first frame at the same time, 1 without any, saturating
+	/// rather than overflowing for animations that hardly ever line up
+	pub fn animation_period(&self) -> u32 {
+		crate::animation::common_period(&self.animations)
+	}
+
+	/// Moves both meshes over by `block_offset` whole blocks, see [`translate_mesh`]
+	pub fn translate(&mut self, block_offset: Vec3) {
+		translate_mesh(&mut self.mesh, block_offset);
+		if let Some(translucent_mesh) = &mut self.translucent_mesh {
+			translate_mesh(translucent_mesh, block_offset);
 		}
-		if self.faces.east.is_some() {
-			count += 1;
+	}
+
+	/// `texture` with every animated texture at how it looks `tick` ticks in
+	pub fn texture_at_tick(&self, tick: u32) -> anyhow::Result<CpuTexture> {
+		let mut image = self.atlas_image()?;
+		for animation in &self.animations {
+			let [x, y, ..] = animation.rect;
+			let padding = animation.padding;
+			image.copy_from(&padded(&animation.frame_at(tick), padding), x - padding, y - padding)?;
 		}
-		if self.faces.south.is_some() {
-			count += 1;
+		Ok(CpuTexture {
+			data: TextureData::RgbaU8(image.pixels().map(|p| p.0).collect()),
+			..self.texture.clone()
+		})
+	}
+}
+
+/// Moves a mesh over by `block_offset` blocks of 16 model units each, to put models built on their own next to each
+/// other the way they'd be placed in the world
+pub fn translate_mesh(mesh: &mut CpuMesh, block_offset: Vec3) {
+	let offset = block_offset * 16.0;
+	match &mut mesh.positions {
+		Positions::F32(positions) => positions.iter_mut().for_each(|position| *position += offset),
+		Positions::F64(positions) => positions
+			.iter_mut()
+			.for_each(|position| *position += offset.cast().expect("f32 fits in f64")),
+	}
+}
+
+/// A texture that couldn't be loaded for the atlas
+#[derive(Clone, Debug)]
+pub struct TextureLoadError {
+	/// The texture variable it was loaded for, like `all`, other variables pointing at the same texture aren't listed
+	pub id: String,
+	/// The texture it refers to, like `minecraft:block/stone`
+	pub path: String,
+	pub source: Arc<dyn std::error::Error + Send + Sync>,
+}
+
+impl std::fmt::Display for TextureLoadError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if self.path.is_empty() || self.path == self.id {
+			write!(f, "unable to load texture {}: {}", self.id, self.source)?;
+		} else {
+			write!(f, "unable to load texture {}: {}: {}", self.id, self.path, self.source)?;
 		}
-		if self.faces.west.is_some() {
-			count += 1;
+		// Some errors already repeat their cause in their own message
+		let (mut last, mut cause) = (self.source.to_string(), self.source.source());
+		while let Some(error) = cause {
+			let message = error.to_string();
+			if !last.ends_with(&message) {
+				write!(f, ": {message}")?;
+			}
+			(last, cause) = (message, error.source());
 		}
-		if self.faces.up.is_some() {
-			count += 1;
+		Ok(())
+	}
+}
+
+impl std::error::Error for TextureLoadError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		Some(&*self.source)
+	}
+}
+
+/// The ways loading and meshing a model can go wrong, what parsing, resolving parents, [`ModelLoader`], the
+/// [`AtlasBuilder`] and building meshes fail with so callers can match on it to retry or skip rather than just report.
+/// The [`ModelSource`] and [`TextureSource`] traits are left returning `anyhow::Result` to be implemented however
+/// suits, errors out of a model source end up in [`McModelError::ModelSource`] unless they're one of these already.
+#[derive(Debug, thiserror::Error)]
+pub enum McModelError {
+	#[error("invalid model json: {0}")]
+	Parse(serde_json::Error),
+	/// A model file that couldn't be read
+	#[error("unable to open {path:?}")]
+	Io {
+		path: PathBuf,
+		#[source]
+		source: std::io::Error,
+	},
+	/// Same as `Parse` but for a model file, with its path
+	#[error("unable to parse model at {path:?}")]
+	ParseFile {
+		path: PathBuf,
+		#[source]
+		source: serde_json::Error,
+	},
+	/// A model the source doesn't have at all, as opposed to one it has but can't read or parse
+	#[error("no model {location} at {searched}")]
+	ModelNotFound {
+		/// The model, like `minecraft:block/cube`
+		location: String,
+		/// Where it was looked for
+		searched: String,
+	},
+	/// Whatever else a [`ModelSource`] failed to load a model with
+	#[error("unable to load model {location}")]
+	ModelSource {
+		location: String,
+		#[source]
+		source: anyhow::Error,
+	},
+	/// Keys the strict parse doesn't know, with the path to each
+	#[error("unknown key(s) in model: {}", .0.join(", "))]
+	UnknownKeys(Vec<String>),
+	#[error("parent cycle detected at {0}")]
+	ParentCycle(String),
+	/// The texture variables that reference each other in a loop, starting and ending at the same one
+	#[error("texture variables reference each other in a cycle: #{}", .0.join(" -> #"))]
+	TextureCycle(Vec<String>),
+	/// A texture the source doesn't have at all, as opposed to one it has but can't decode
+	#[error("no texture file at {searched}")]
+	TextureNotFound {
+		/// The texture, like `minecraft:block/stone`
+		id: String,
+		/// Where it was looked for
+		searched: String,
+	},
+	/// An image bigger than even the largest atlas, which no amount of growing it would fit
+	#[error("texture {id} is {width}x{height} which is too large for the atlas, it can be at most {max}x{max}")]
+	TextureTooLarge {
+		/// The texture, like `minecraft:block/stone`, or `not-found` for the fallback texture
+		id: String,
+		width: u32,
+		height: u32,
+		max: u32,
+	},
+	/// An image without a single pixel, which has no rectangle to go in on the atlas
+	#[error("texture {id} is empty at {width}x{height}")]
+	EmptyImage {
+		/// The texture, like `minecraft:block/stone`, or `not-found` for the fallback texture
+		id: String,
+		width: u32,
+		height: u32,
+	},
+	/// Atlas padding that leaves no room on even the largest atlas for a single pixel between it, at most
+	/// [`MAX_ATLAS_PADDING`]
+	#[error("atlas padding of {0} is too large, it can be at most {MAX_ATLAS_PADDING}")]
+	PaddingTooLarge(u32),
+	/// The atlas grew to [`MAX_ATLAS_SIZE`] and there still wasn't room
+	#[error("the atlas is full at its largest size of {0}x{0}")]
+	AtlasFull(u32),
+	#[error("unsupported face rotation: {0}, must be a multiple of 90")]
+	UnsupportedFaceRotation(i16),
+	#[error("unsupported blockstate rotation: {0}, must be a multiple of 90")]
+	UnsupportedBlockstateRotation(i16),
+	/// A model that one of Forge's or NeoForge's custom model loaders builds, named by its `loader` key (like
+	/// `forge:obj`), or marked as being in Forge's own format by `forge_marker`.  Whatever geometry those have isn't
+	/// vanilla elements, so rather than drawing them as if it were this is what building a mesh of one fails with.
+	#[error("model needs the custom model loader `{0}`, which isn't supported")]
+	UnsupportedLoader(String),
+	/// A model with a `builtin/entity` parent, whose looks are all in the game's code with nothing of it in the json.
+	/// Building a mesh of one fails with this so callers can tell it apart and draw a stand in of their own.
+	#[error("model is drawn by the game's code for `{BUILTIN_ENTITY}`, it has no geometry of its own")]
+	BuiltinEntityModel,
+	/// Only with `MeshOptions::strict`, otherwise a placeholder cube is drawn instead
+	#[error("model has no renderable faces")]
+	NoRenderableFaces,
+	/// More elements than [`MeshLimits::max_elements`]
+	#[error("model has {count} elements, more than the limit of {max}")]
+	TooManyElements { count: usize, max: usize },
+	/// More faces than [`MeshLimits::max_faces`]
+	#[error("model has {count} faces, more than the limit of {max}")]
+	TooManyFaces { count: usize, max: usize },
+	/// More distinct vertices than a `CpuMesh` can index
+	#[error("too many vertices for a mesh: {0}")]
+	TooManyVertices(usize),
+	/// A texture variable set to an empty string, which hand edited models leave behind
+	#[error("the texture is empty")]
+	EmptyTexture,
+	/// A face's `#variable` that neither the model nor any of its parents define
+	#[error("texture variable `#{0}` isn't defined")]
+	UndefinedTextureVariable(String),
+	/// A face's `texture` that isn't a `#variable` reference at all
+	#[error("face texture `{0}` should be a `#variable` reference")]
+	NotATextureVariable(String),
+}
+
+impl McModelError {
+	/// An error out of a [`ModelSource`] loading `location`, taken as it is when it already is one of these without
+	/// any context on top, so nothing said about it gets lost
+	fn from_model_source(location: &str, error: anyhow::Error) -> McModelError {
+		if error.chain().next().is_some_and(|outermost| outermost.is::<McModelError>()) {
+			return error.downcast().expect("the outermost error was checked to be one");
 		}
-		if self.faces.down.is_some() {
-			count += 1;
+		McModelError::ModelSource { location: location.to_string(), source: error }
+	}
+}
+
+/// Size of a model's mesh, see `McModelJson::estimate_mesh_stats`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MeshStats {
+	pub faces: usize,
+	pub triangles: usize,
+	pub max_vertices: usize,
+}
+
+/// A model's mesh as flat arrays to upload as they are, see `McModelJson::to_mesh_buffers`.
+///
+/// Every face is a quad of four vertices of its own (or a few quads with [`UvMode::Clamp`]), grouped by the side of
+/// its element it is in north, east, south, west, up, down order and in element order within each side, so quad `n` is
+/// vertices `4n..4n + 4` and indices `6n..6n + 6` and is the face `faces[n]`.  Its vertices go counter-clockwise as
+/// seen from the front like the `CpuMesh` ones do, and the indices are always its two triangles `0 1 2` and `2 3 0`.  A
+/// model without any faces comes out as the placeholder cube, with `faces` all saying element 0, and a
+/// `builtin/generated` item as its extruded layers with `element_idx` being the layer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshBuffers {
+	/// `x, y, z` of each vertex in the 0-16 model space, after the element and blockstate rotations
+	pub positions: Vec<f32>,
+	/// `u, v` of each vertex on the atlas, 0-1 across it with `v` going down
+	pub uvs: Vec<f32>,
+	/// `r, g, b, a` of each vertex from 0 to 1, the baked shading to multiply the texture with
+	pub colors: Vec<f32>,
+	/// `x, y, z` unit normal of each vertex
+	pub normals: Vec<f32>,
+	pub indices: Vec<u32>,
+	pub faces: Vec<MeshBufferFace>,
+}
+
+impl MeshBuffers {
+	/// Floats per vertex in [`MeshBuffers::interleaved`]
+	pub const STRIDE: usize = 12;
+
+	pub fn vertex_count(&self) -> usize {
+		self.positions.len() / 3
+	}
+
+	/// Every attribute of a vertex one after the other, [`Self::STRIDE`] floats each laid out as position `x, y, z`
+	/// at 0, uv `u, v` at 3, color `r, g, b, a` at 5 and normal `x, y, z` at 9
+	pub fn interleaved(&self) -> Vec<f32> {
+		let mut interleaved = Vec::with_capacity(self.vertex_count() * Self::STRIDE);
+		for vertex in 0..self.vertex_count() {
+			interleaved.extend_from_slice(&self.positions[vertex * 3..vertex * 3 + 3]);
+			interleaved.extend_from_slice(&self.uvs[vertex * 2..vertex * 2 + 2]);
+			interleaved.extend_from_slice(&self.colors[vertex * 4..vertex * 4 + 4]);
+			interleaved.extend_from_slice(&self.normals[vertex * 3..vertex * 3 + 3]);
 		}
-		count
+		interleaved
 	}
+}
 
-	pub fn transformation(&self) -> Mat4 {
-		if let Some(rot) = &self.rotation {
-			let mat = match rot.axis {
-				McModelRotationAxis::X => Mat4::from_angle_x(Deg(rot.angle as f32)),
-				McModelRotationAxis::Y => Mat4::from_angle_y(Deg(rot.angle as f32)),
-				McModelRotationAxis::Z => Mat4::from_angle_z(Deg(rot.angle as f32)),
-			};
-			mat + Mat4::from_translation(Vec3::new(rot.origin[0] as f32, rot.origin[1] as f32, rot.origin[2] as f32))
+/// Which face of the model a quad of [`MeshBuffers`] is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MeshBufferFace {
+	pub element_idx: usize,
+	/// The side of the element it's on in the model, however the blockstate rotation turned it
+	pub direction: McModelDirection,
+}
+
+/// A quad's corners and their atlas uvs
+type FaceQuad = ([Vector3<f64>; 4], [[f64; 2]; 4]);
+
+/// The quads a face with `corners` and atlas `uvs` is drawn as for [`UvMode::Clamp`], `start` being the atlas uv the
+/// texture stays pinned to.  Both go corner 0 to 1 along one side of the face and 1 to 2 along the other, so each side
+/// is done on its own by how long it is against how many uv units it runs over.
+fn clamped_quads(
+	face: &McModelFace,
+	rotated: bool,
+	corners: [Vector3<f64>; 4],
+	uvs: [[f64; 2]; 4],
+	start: [f64; 2],
+) -> Vec<FaceQuad> {
+	let [u0, v0, u1, v1] = face.uv.expect("face uv is filled in by variant_face");
+	// Which of u and v runs along each side, a quarter turn swaps them over
+	let (along_a, along_b) = if rotated { (0, 1) } else { (1, 0) };
+	let extents = [(u1 - u0).abs(), (v1 - v0).abs()];
+	// The points along a side to split it at with the uv position at each, both from 0 at its first corner to 1 at its
+	// other, the stretch of it past the uv's extent staying on the uv's last texel
+	let splits = |length: f64, coord: usize, first: [f64; 2], second: [f64; 2]| -> Vec<(f64, f64)> {
+		let extent = extents[coord];
+		if extent <= 0.0 || length <= 0.0 {
+			return vec![(0.0, 0.0), (1.0, 1.0)];
+		}
+		let fit = extent / length;
+		if (first[coord] - start[coord]).abs() <= (second[coord] - start[coord]).abs() {
+			if fit < 1.0 {
+				vec![(0.0, 0.0), (fit, 1.0), (1.0, 1.0)]
+			} else {
+				vec![(0.0, 0.0), (1.0, 1.0 / fit)]
+			}
+		} else if fit < 1.0 {
+			vec![(0.0, 0.0), (1.0 - fit, 0.0), (1.0, 1.0)]
 		} else {
-			Mat4::identity()
+			vec![(0.0, 1.0 - 1.0 / fit), (1.0, 1.0)]
+		}
+	};
+	let splits_a = splits((corners[1] - corners[0]).magnitude(), along_a, uvs[0], uvs[1]);
+	let splits_b = splits((corners[2] - corners[1]).magnitude(), along_b, uvs[1], uvs[2]);
+	let pos = |a: f64, b: f64| corners[0] + (corners[1] - corners[0]) * a + (corners[2] - corners[1]) * b;
+	let uv = |a: f64, b: f64| [0, 1].map(|c| uvs[0][c] + (uvs[1][c] - uvs[0][c]) * a + (uvs[2][c] - uvs[1][c]) * b);
+	let mut quads = Vec::new();
+	for a in splits_a.windows(2) {
+		for b in splits_b.windows(2) {
+			let ((a0, ua0), (a1, ua1), (b0, ub0), (b1, ub1)) = (a[0], a[1], b[0], b[1]);
+			quads.push((
+				[pos(a0, b0), pos(a1, b0), pos(a1, b1), pos(a0, b1)],
+				[uv(ua0, ub0), uv(ua1, ub0), uv(ua1, ub1), uv(ua0, ub1)],
+			));
 		}
 	}
+	quads
 }
 
-impl McModelJson {
-	pub fn parse_json_model_slice(json_data: &[u8]) -> anyhow::Result<McModelJson> {
-		Ok(serde_json::from_slice(json_data)?)
+/// Where a ray struck a model, see `McModelJson::raycast`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+	pub element_idx: usize,
+	/// Which of the element's faces it went in through
+	pub direction: McModelDirection,
+	/// How far along the ray that is in model units
+	pub distance: f32,
+	/// Where on the face that is, the same 0-16 across the face that `McModelJson::sample_face_color` takes
+	pub uv: [f64; 2],
+}
+
+/// How much of a texture's alpha matters for drawing it, ordered from the least to the most demanding
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TextureAlpha {
+	/// Every pixel is fully opaque
+	Opaque,
+	/// Pixels are either fully opaque or fully transparent, like leaves and plants, which alpha testing draws exactly
+	Cutout,
+	/// Some pixels are partially transparent, like stained glass, those need blending
+	Translucent,
+}
+
+impl TextureAlpha {
+	pub fn of_image(image: &RgbaImage) -> TextureAlpha {
+		image.pixels().fold(TextureAlpha::Opaque, |alpha, pixel| match pixel.0[3] {
+			u8::MAX => alpha,
+			0 => alpha.max(TextureAlpha::Cutout),
+			_ => TextureAlpha::Translucent,
+		})
 	}
+}
+
+/// What `McModelJson::sample_face_color` does to a texel on top of reading it
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SampleOptions {
+	/// Darken it by the face's direction the same as the baked shading of `to_cpu_mesh`, emissive faces and elements
+	/// with `shade` off excepted
+	pub shading: bool,
+	/// Multiplied into faces with a `tintindex`, like the grass or foliage color the game would use
+	pub tint: Option<Srgba>,
+}
+
+/// Knobs for how `McModelJson::to_cpu_mesh_with_options` builds a mesh
+#[derive(Clone, Debug, Default)]
+pub struct MeshOptions {
+	pub rotation: McBlockstateRotation,
+	/// Error out on models without any faces instead of substituting a not-found checkerboard cube
+	pub strict: bool,
+	/// What faces get textured with when their texture couldn't be loaded
+	pub fallback: FallbackTexture,
+	/// Skip the per-direction face shading, like MC does for `gui_light: front` models in the GUI
+	pub flat_lighting: bool,
+	/// Bake corner darkening from neighboring elements into the vertex colors, this checks every element against
+	/// every face corner so it can get slow on large models.  Models with `ambientocclusion: false` never get it.
+	pub ao: bool,
+	/// How the atlas texture gets sampled
+	pub filter: FilterMode,
+	/// Whether element coordinates outside of what vanilla accepts get clamped or used as they are
+	pub coordinates: CoordinateRange,
+	/// Give the atlas texture mipmaps so that shrunk down models don't shimmer, each texture is packed with a
+	/// [`MIPMAP_PADDING`] border of its own edge pixels so the lower levels don't pull in their atlas neighbors
+	pub mipmaps: bool,
+	/// Pixels of its own edges repeated around each texture on the atlas, the same as [`AtlasBuilder::with_padding`],
+	/// `None` for [`MIPMAP_PADDING`] with `mipmaps` and none without.  Linear filtering without mipmaps can also do
+	/// with a pixel or two to keep the neighbors out for good rather than relying on `uv_inset` alone.
+	pub atlas_padding: Option<u32>,
+	/// Put faces showing any texel with an alpha below this into [`McModelMesh::translucent_mesh`] and keep the rest
+	/// in `mesh`, so the opaque part can be drawn without blending first and the translucent part blended over it
+	pub translucency_split: Option<u8>,
+	/// How many atlas texels the uvs get pulled in from each texture's edges so that its neighbors on the atlas don't
+	/// bleed in at the borders, `None` for what suits `filter`: half a texel for linear filtering, which blends in
+	/// that much around the sampled point, and a sixteenth for nearest, which only needs to stay off the exact edge.
+	/// More inset keeps the neighbors out more reliably but visibly shrinks the texture's edge texels on large faces,
+	/// `Some(0.0)` maps the texels exactly at the cost of the odd stray edge line.
+	pub uv_inset: Option<f64>,
+	/// Fill in the mesh's tangents, which only a normal mapped material needs and which take a good part of the
+	/// build time to work out, so they're left out otherwise
+	pub tangents: bool,
+	/// Sides of the block that have a full block against them, after `rotation`.  Faces with a `cullface` that ends up
+	/// on one of these are left out like the game does: `cullface` is in the model's own space, so it turns with the
+	/// blockstate `rotation` but not with the element's own `rotation`, which only ever moves the face within the block
+	pub culled: Vec<McModelDirection>,
+	/// How a face's `uv` is fitted onto it when the two aren't the same size
+	pub uv_mode: UvMode,
+	/// What the per-direction shading and ambient occlusion darken, the encoded colors like the game or the light
+	pub color_space: ColorSpace,
+	/// How large a model gets built at all, so one with millions of elements fails instead of running out of memory
+	pub limits: MeshLimits,
+}
+
+/// Caps on the size of the models a mesh gets built of, failing with [`McModelError::TooManyElements`] or
+/// [`McModelError::TooManyFaces`] past them.  They're checked before any of the model's textures are loaded or an
+/// atlas is allocated for it, meshes built on an atlas of the caller's only get checked once its textures are on it.
+/// The defaults are well beyond what even big modded models have, services rendering models anyone can upload may
+/// want to go lower.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MeshLimits {
+	pub max_elements: usize,
+	/// Counted over all the elements before any get culled
+	pub max_faces: usize,
+}
+
+impl MeshLimits {
+	/// No caps at all, for models that are known to be fine
+	pub const UNLIMITED: MeshLimits = MeshLimits { max_elements: usize::MAX, max_faces: usize::MAX };
 
-	pub fn parse_json_model_from_reader(json_data: impl std::io::Read) -> anyhow::Result<McModelJson> {
-		Ok(serde_json::from_reader(json_data)?)
+	fn check(&self, model: &McModelJson) -> Result<(), McModelError> {
+		if model.elements.len() > self.max_elements {
+			return Err(McModelError::TooManyElements { count: model.elements.len(), max: self.max_elements });
+		}
+		let face_count = model.face_count();
+		if face_count > self.max_faces {
+			return Err(McModelError::TooManyFaces { count: face_count, max: self.max_faces });
+		}
+		Ok(())
 	}
+}
 
-	pub fn face_count(&self) -> usize {
-		let mut count = 0;
-		for element in &self.elements {
-			if element.faces.north.is_some() {
-				count += 1;
-			}
-			if element.faces.east.is_some() {
-				count += 1;
+impl Default for MeshLimits {
+	fn default() -> Self {
+		MeshLimits { max_elements: 16384, max_faces: 65536 }
+	}
+}
+
+/// What vanilla accepts for element `from` and `to` coordinates, in 1/16th of a block
+pub const VANILLA_COORDINATE_RANGE: std::ops::RangeInclusive<f64> = -16.0..=32.0;
+
+/// How element coordinates outside of [`VANILLA_COORDINATE_RANGE`] are treated, the mesh itself copes with any
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CoordinateRange {
+	/// Use them as they are, plenty of mods go beyond vanilla's range
+	#[default]
+	PassThrough,
+	/// Clamp them into vanilla's range like the game would have to, with a warning for each element clamped
+	ClampVanilla,
+}
+
+/// How the `uv` rectangle of a face maps onto a face of another size, like a `[0, 0, 4, 4]` uv on a face 8 units wide
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UvMode {
+	/// Spread the uv over the whole face whatever its size, which is what the game does
+	#[default]
+	Stretch,
+	/// Show the texture at its own scale of 16 uv units to the block from the uv's first corner on, cutting it short on
+	/// faces smaller than the uv and stretching its last row and column of texels over the rest of larger ones.  The
+	/// atlas's own `ClampToEdge` wrapping only applies at the edges of the whole atlas, so a face gets split into up to
+	/// four quads for this instead and shows up that many times in [`MeshBuffers::faces`].  Faces with `uvlock` already
+	/// map the texture by where they are and are left as they are.
+	Clamp,
+}
+
+/// Which values of a color the baked shading multiplies, the renderers decode the vertex colors to linear either way
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+	/// The sRGB encoded values, like the game does, so a down face's 0.5 shows its texture at half the brightness
+	#[default]
+	Srgb,
+	/// The linear light they stand for, which is physically right but comes out brighter than in game: the down face
+	/// shows around 74% of its texture's encoded values, east and west 80% and north and south 91%
+	Linear,
+}
+
+impl ColorSpace {
+	/// The vertex color of a face with the direction shading `mult`
+	fn shading_srgba(self, mult: f32) -> Srgba {
+		match self {
+			ColorSpace::Srgb => Srgba::from([mult, mult, mult, 1.0]),
+			ColorSpace::Linear => {
+				let c = (raster::srgb_from_linear(mult) * 255.0).round() as u8;
+				Srgba::new(c, c, c, 255)
 			}
-			if element.faces.south.is_some() {
-				count += 1;
+		}
+	}
+
+	/// `color` darkened by `mult` in this color space
+	fn darken(self, color: Srgba, mult: f32) -> Srgba {
+		match self {
+			ColorSpace::Srgb => {
+				let darken = |c: u8| (c as f32 * mult).round() as u8;
+				Srgba::new(darken(color.r), darken(color.g), darken(color.b), color.a)
 			}
-			if element.faces.west.is_some() {
-				count += 1;
+			ColorSpace::Linear => {
+				let linear = color.to_linear_srgb();
+				let darken = |c: f32| (raster::srgb_from_linear(c * mult) * 255.0).round() as u8;
+				Srgba::new(darken(linear.x), darken(linear.y), darken(linear.z), color.a)
 			}
-			if element.faces.up.is_some() {
-				count += 1;
+		}
+	}
+}
+
+/// Texture filtering of the atlas
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FilterMode {
+	/// Crisp pixels, what MC's own textures are made for
+	#[default]
+	Nearest,
+	/// Smooth blending between texels, for high resolution packs that expect it
+	Linear,
+}
+
+impl FilterMode {
+	pub fn interpolation(self) -> Interpolation {
+		match self {
+			FilterMode::Nearest => Interpolation::Nearest,
+			FilterMode::Linear => Interpolation::Linear,
+		}
+	}
+}
+
+/// Stand-in for textures that failed to load
+#[derive(Clone, Debug)]
+pub enum FallbackTexture {
+	/// The classic magenta and black checkerboard, `size` pixels square, with the squares scaled along so that it
+	/// still reads as a 16x16 pattern
+	Checkerboard { size: u32 },
+	Solid(Srgba),
+	Image(RgbaImage),
+}
+
+impl Default for FallbackTexture {
+	fn default() -> Self {
+		FallbackTexture::Checkerboard { size: 16 }
+	}
+}
+
+impl FallbackTexture {
+	pub fn to_image(&self) -> RgbaImage {
+		match self {
+			FallbackTexture::Checkerboard { size } => {
+				let cell = (*size / 16).max(1);
+				RgbaImage::from_fn(*size, *size, |x, y| {
+					if (x / cell + y / cell) % 2 == 0 {
+						image::Rgba([255, 0, 255, 255])
+					} else {
+						image::Rgba([0, 0, 0, 255])
+					}
+				})
 			}
-			if element.faces.down.is_some() {
-				count += 1;
+			FallbackTexture::Solid(color) => {
+				RgbaImage::from_pixel(16, 16, image::Rgba([color.r, color.g, color.b, color.a]))
 			}
+			FallbackTexture::Image(image) => image.clone(),
 		}
-		count
+	}
+}
+
+/// The whole-model rotation a blockstate variant applies on top of its model
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct McBlockstateRotation {
+	#[serde(default, skip_serializing_if = "num_traits::identities::Zero::is_zero")]
+	pub x: i16,
+	#[serde(default, skip_serializing_if = "num_traits::identities::Zero::is_zero")]
+	pub y: i16,
+	/// Keep textures world-aligned instead of letting them rotate along with the geometry
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub uvlock: bool,
+}
+
+impl McBlockstateRotation {
+	pub fn is_identity(&self) -> bool {
+		self.x.rem_euclid(360) == 0 && self.y.rem_euclid(360) == 0
 	}
 
-	pub fn to_cpu_mesh(&self, texture_base_path: &Path) -> anyhow::Result<(CpuMesh, CpuTexture)> {
-		// Texture building
-		let mut atlas_mappings = HashMap::with_capacity(self.textures.len());
-		let err_tex;
-		let texture = {
-			use etagere::*;
-			let mut tex = RgbaImage::new(2048, 2048);
-			let mut atlas = AtlasAllocator::new(size2(2048, 2048));
-			err_tex = atlas.allocate(size2(16, 16)).with_context(|| "unable to allocate not-found 16x16 space on atlas")?;
-			for x in err_tex.rectangle.min.x..err_tex.rectangle.max.x {
-				for y in err_tex.rectangle.min.y..err_tex.rectangle.max.y {
-					tex.put_pixel(x as u32, y as u32, if (x + y) % 2 == 0 { image::Rgba([255, 0, 255, 255]) } else { image::Rgba([0, 0, 0, 255]) });
-				}
+	/// Rotation matrix about the block origin, MC applies the `x` rotation first and then the `y` one
+	pub fn transformation(&self) -> Result<Matrix4<f64>, McModelError> {
+		// Built by hand instead of `from_angle_*` so that the quarter turns stay exact, otherwise the vertex dedup
+		// would see `6.1e-17` instead of `0.0` and such
+		// MC rotates clockwise when looking down the axis, so this is the cosine and sine of the negated angle.  The
+		// angle is only reduced and never negated itself, `-i16::MIN` doesn't fit in an `i16`.
+		fn quarter_turn(angle: i16) -> Result<(f64, f64), McModelError> {
+			match angle.rem_euclid(360) {
+				0 => Ok((1.0, 0.0)),
+				90 => Ok((0.0, -1.0)),
+				180 => Ok((-1.0, 0.0)),
+				270 => Ok((0.0, 1.0)),
+				_ => Err(McModelError::UnsupportedBlockstateRotation(angle)),
 			}
-			for (tex_id, tex_path) in &self.textures {
-				let texture_path = {
-					let mut texture_path = tex_path.clone();
-					texture_path.push_str(".png");
-					texture_base_path.join(&texture_path)
-				};
-				if let Ok(tile) = image::open(&texture_path) {
-					let tile = tile.to_rgba8();
-					let mapping = atlas.allocate(size2(tile.width() as i32, tile.height() as i32)).with_context(|| format!("unable to store {tex_id} image on atlas from: {tex_path}"))?;
-					tex.copy_from(&tile, mapping.rectangle.min.x as u32, mapping.rectangle.min.y as u32)?;
-					atlas_mappings.insert(tex_id.clone(), mapping);
-				} else {
-					eprintln!("unable to open texture: {texture_path:?}");
-				}
+		}
+		let (c, s) = quarter_turn(self.x)?;
+		let rot_x = Matrix3::new(1.0, 0.0, 0.0, 0.0, c, s, 0.0, -s, c);
+		let (c, s) = quarter_turn(self.y)?;
+		let rot_y = Matrix3::new(c, 0.0, -s, 0.0, 1.0, 0.0, s, 0.0, c);
+		Ok(Matrix4::from(rot_y * rot_x))
+	}
+}
+
+/// A `namespace:path` style reference to a model or texture, the namespace defaults to `minecraft` when left out
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct McResourceLocation {
+	pub namespace: String,
+	pub path: String,
+}
+
+impl McResourceLocation {
+	pub fn parse(location: &str) -> McResourceLocation {
+		if let Some((namespace, path)) = location.split_once(':') {
+			McResourceLocation {
+				namespace: namespace.to_string(),
+				path: path.to_string(),
 			}
-			// image::save_buffer("atlas.png", tex.as_raw(), tex.width(), tex.height(), image::ColorType::Rgba8)?;
-			CpuTexture {
-				name: "atlas".to_string(),
-				data: TextureData::RgbaU8(tex.pixels().map(|p| p.0).collect()),
-				width: tex.width(),
-				height: tex.height(),
-				min_filter: Interpolation::Nearest,
-				mag_filter: Interpolation::Nearest,
-				mip_map_filter: None,
-				wrap_s: Wrapping::ClampToEdge,
-				wrap_t: Wrapping::ClampToEdge,
+		} else {
+			McResourceLocation {
+				namespace: "minecraft".to_string(),
+				path: location.to_string(),
 			}
-		};
+		}
+	}
+}
 
-		// Mesh building
-		// Don't normally do this with floats unless you understand the dangers involved
-		#[derive(PartialEq)]
-		struct Vec3S {
-			x: f64,
-			y: f64,
-			z: f64,
-			u: f64,
-			v: f64,
-			color: Srgba,
-		}
-		impl Eq for Vec3S {}
-		impl Hash for Vec3S {
-			fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-				self.x.to_bits().hash(state);
-				self.y.to_bits().hash(state);
-				self.z.to_bits().hash(state);
-				self.u.to_bits().hash(state);
-				self.v.to_bits().hash(state);
-				self.color.hash(state);
-			}
-		}
-		let mut datas = IndexMap::with_capacity(self.elements.len() * 36);
-		let mut indices = Vec::with_capacity(self.elements.len() * 36);
-
-		let mut push_pos = |x: f64, y: f64, z: f64, u: f64, v: f64, color: Srgba| {
-			let pos = Vec3S { x, y, z, u, v, color };
-			if let Some((idx, _, ())) = datas.get_full(&pos) {
-				indices.push(idx as u32);
-			} else {
-				indices.push(datas.len() as u32);
-				datas.insert(pos, ());
-			};
-		};
+impl std::fmt::Display for McResourceLocation {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}:{}", self.namespace, self.path)
+	}
+}
 
-		let bleed = (2048.0f64 * 16.0).recip();
-		let get_uv = |face: &McModelFace| -> anyhow::Result<(bool, [f64; 4])> {
-			let offset = if let Some(atlas_mapping) = atlas_mappings.get(face.texture.strip_prefix('#').context("texture ID should start with '#'")?) {
-				atlas_mapping.rectangle
-			} else {
-				err_tex.rectangle
-			};
-			let [u0, v0, u1, v1] = face.uv;
-			let [v0, v1] = [v1, v0];
-			let (flip, [u0, v0, u1, v1]) =
-				match face.rotation {
-					0 => (false, [u0, v0, u1, v1]),
+/// A resource pack (or extracted game jar) laid out as `<root>/assets/<namespace>/{models,textures}/<path>`
+#[derive(Clone, Debug)]
+pub struct McAssetsRoot {
+	pub root: PathBuf,
+}
+
+impl McAssetsRoot {
+	pub fn new(root: impl Into<PathBuf>) -> McAssetsRoot {
+		McAssetsRoot { root: root.into() }
+	}
+
+	pub fn model_path(&self, location: &str) -> PathBuf {
+		let location = McResourceLocation::parse(location);
+		self.root.join("assets").join(&location.namespace).join("models").join(format!("{}.json", location.path))
+	}
+
+	pub fn texture_path(&self, location: &str) -> PathBuf {
+		with_appended_extension(&self.texture_path_stem(location), "png")
+	}
+
+	/// The first file that exists for the texture across [`TEXTURE_EXTENSIONS`], its extension being the one that
+	/// gets loaded
+	pub fn find_texture_path(&self, location: &str) -> Option<PathBuf> {
+		find_with_extensions(&self.texture_path_stem(location), TEXTURE_EXTENSIONS.iter().copied())
+	}
+
+	fn texture_path_stem(&self, location: &str) -> PathBuf {
+		let location = McResourceLocation::parse(location);
+		self.root.join("assets").join(&location.namespace).join("textures").join(location.path)
+	}
+
+	/// Loads a single model file without touching its parents, [`McModelError::ModelNotFound`] if there's no such file
+	pub fn load_model(&self, location: &str) -> Result<McModelJson, McModelError> {
+		let path = self.model_path(location);
+		match McModelJson::parse_json_model_file(&path) {
+			Err(McModelError::Io { source, .. }) if source.kind() == std::io::ErrorKind::NotFound => {
+				Err(McModelError::ModelNotFound { location: location.to_string(), searched: describe_path(&path) })
+			}
+			result => result,
+		}
+	}
+
+	/// Loads a model and merges its whole parent chain into it
+	pub fn resolve_model(&self, location: &str) -> Result<McModelJson, McModelError> {
+		self.load_model(location)?.resolve_parents(self)
+	}
+}
+
+/// Supplies model json by resource location, so parents can be resolved out of whatever holds the pack (a directory,
+/// an archive, or something the caller made up)
+pub trait ModelSource {
+	/// Loads a single model without touching its parents
+	fn load_model(&self, location: &str) -> anyhow::Result<McModelJson>;
+
+	/// Loads a model and merges its whole parent chain into it
+	fn resolve_model(&self, location: &str) -> Result<McModelJson, McModelError>
+	where
+		Self: Sized,
+	{
+		let model = self.load_model(location).map_err(|error| McModelError::from_model_source(location, error))?;
+		model.resolve_parents(self)
+	}
+}
+
+impl ModelSource for McAssetsRoot {
+	fn load_model(&self, location: &str) -> anyhow::Result<McModelJson> {
+		Ok(McAssetsRoot::load_model(self, location)?)
+	}
+}
+
+/// Loads models out of an assets root (or any other [`ModelSource`]) with their parents resolved, remembering every
+/// model it resolved along the way so that shared parents like `block/cube_all` are only read and parsed once across
+/// a whole pack
+#[derive(Clone, Debug)]
+pub struct ModelLoader<S = McAssetsRoot> {
+	pub assets: S,
+	cache: HashMap<String, Arc<McModelJson>>,
+	loading: Vec<McResourceLocation>,
+}
+
+impl<S: ModelSource> ModelLoader<S> {
+	pub fn new(assets: S) -> ModelLoader<S> {
+		ModelLoader {
+			assets,
+			cache: HashMap::new(),
+			loading: Vec::new(),
+		}
+	}
+
+	/// The model with its whole parent chain merged, like [`McAssetsRoot::resolve_model`] but cached
+	pub fn load(&mut self, location: &str) -> Result<Arc<McModelJson>, McModelError> {
+		let location = McResourceLocation::parse(location);
+		let key = location.to_string();
+		if let Some(model) = self.cache.get(&key) {
+			return Ok(model.clone());
+		}
+		if self.loading.contains(&location) {
+			return Err(McModelError::ParentCycle(location.to_string()));
+		}
+		self.loading.push(location);
+		let resolved = self.load_uncached(&key);
+		self.loading.pop();
+		let model = Arc::new(resolved?);
+		self.cache.insert(key, model.clone());
+		Ok(model)
+	}
+
+	fn load_uncached(&mut self, location: &str) -> Result<McModelJson, McModelError> {
+		let mut model = self
+			.assets
+			.load_model(location)
+			.map_err(|error| McModelError::from_model_source(location, error))?;
+		match model.parent.take() {
+			Some(parent) if !McResourceLocation::parse(&parent).path.starts_with("builtin/") => {
+				let parent_model = self.load(&parent)?;
+				model.merge_parent(McModelJson::clone(&parent_model));
+			}
+			parent => model.parent = parent,
+		}
+		Ok(model)
+	}
+
+	/// How many resolved models are currently cached
+	pub fn cached_count(&self) -> usize {
+		self.cache.len()
+	}
+
+	/// Forgets every cached model, such as after the files on disk changed
+	pub fn clear(&mut self) {
+		self.cache.clear();
+	}
+}
+
+/// Supplies the texture images a model references, `id` being the texture's value such as `minecraft:block/stone`.
+/// Lets callers that can't just read the filesystem (like wasm32 fetching them) hand over textures themselves.
+pub trait TextureSource: Sync {
+	fn load(&self, id: &str) -> Option<RgbaImage>;
+
+	/// Like `load` but saying why the texture couldn't be had, sources that know more than "not found" should override
+	fn load_detailed(&self, id: &str) -> anyhow::Result<RgbaImage> {
+		self.load(id).ok_or_else(|| {
+			let searched = self.describe_location(id).unwrap_or_else(|| id.to_string());
+			McModelError::TextureNotFound { id: id.to_string(), searched }.into()
+		})
+	}
+
+	/// Where the texture is read from, or would be if it's missing, to tell the user about
+	fn describe_location(&self, _id: &str) -> Option<String> {
+		None
+	}
+
+	/// The animation in the texture's `.mcmeta`, `None` when there's no such file or it doesn't animate anything.  A
+	/// broken one is an error like for the game, which won't have the texture then either.
+	fn load_animation(&self, _id: &str) -> anyhow::Result<Option<McTextureAnimation>> {
+		Ok(None)
+	}
+}
+
+/// The animation in the `.mcmeta` next to a texture file, if there is one
+fn load_animation_file(texture_path: &Path) -> anyhow::Result<Option<McTextureAnimation>> {
+	let path = with_appended_extension(texture_path, "mcmeta");
+	match std::fs::read(&path) {
+		Ok(data) => McTextureAnimation::from_mcmeta(&data).with_context(|| format!("unable to read {path:?}")),
+		Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+		Err(error) => Err(error).with_context(|| format!("unable to read {path:?}")),
+	}
+}
+
+/// Texture file extensions that are looked for in order, `png` first as that's all vanilla ships
+pub const TEXTURE_EXTENSIONS: &[&str] = &["png", "tga"];
+
+/// Reads `<base_path>/<path>.<extension>`, dropping any namespace, which fits textures sitting relative to a model file
+#[derive(Clone, Debug)]
+pub struct FsTextureSource {
+	pub base_path: PathBuf,
+	/// Tried in order, defaults to [`TEXTURE_EXTENSIONS`]
+	pub extensions: Vec<String>,
+}
+
+impl FsTextureSource {
+	pub fn new(base_path: impl Into<PathBuf>) -> FsTextureSource {
+		FsTextureSource {
+			base_path: base_path.into(),
+			extensions: TEXTURE_EXTENSIONS.iter().map(|ext| ext.to_string()).collect(),
+		}
+	}
+
+	/// Where the texture would be with the first extension
+	pub fn texture_path(&self, id: &str) -> PathBuf {
+		let extension = self.extensions.first().map_or("png", String::as_str);
+		with_appended_extension(&self.base_path.join(McResourceLocation::parse(id).path), extension)
+	}
+
+	/// The first file that exists for the texture across the extensions, its extension being the one that gets loaded
+	pub fn find_texture_path(&self, id: &str) -> Option<PathBuf> {
+		let path = self.base_path.join(McResourceLocation::parse(id).path);
+		find_with_extensions(&path, self.extensions.iter().map(String::as_str))
+	}
+}
+
+impl TextureSource for FsTextureSource {
+	fn load(&self, id: &str) -> Option<RgbaImage> {
+		self.load_detailed(id).ok()
+	}
+
+	fn load_detailed(&self, id: &str) -> anyhow::Result<RgbaImage> {
+		let path = self.find_texture_path(id).ok_or_else(|| McModelError::TextureNotFound {
+			id: id.to_string(),
+			searched: format!("{:?} with any of {:?}", self.texture_path(id), self.extensions),
+		})?;
+		load_texture_file(&path)
+	}
+
+	fn describe_location(&self, id: &str) -> Option<String> {
+		Some(describe_path(&self.find_texture_path(id).unwrap_or_else(|| self.texture_path(id))))
+	}
+
+	fn load_animation(&self, id: &str) -> anyhow::Result<Option<McTextureAnimation>> {
+		self.find_texture_path(id).map_or(Ok(None), |path| load_animation_file(&path))
+	}
+}
+
+/// Textures already in memory by their id, with or without the `minecraft:` namespace, for rendering without any
+/// files around like in a browser
+impl TextureSource for HashMap<String, RgbaImage> {
+	fn load(&self, id: &str) -> Option<RgbaImage> {
+		if let Some(image) = self.get(id) {
+			return Some(image.clone());
+		}
+		let location = McResourceLocation::parse(id);
+		self.iter().find(|(key, _)| McResourceLocation::parse(key) == location).map(|(_, image)| image.clone())
+	}
+
+	fn describe_location(&self, id: &str) -> Option<String> {
+		Some(format!("{id} in memory"))
+	}
+}
+
+impl TextureSource for McAssetsRoot {
+	fn load(&self, id: &str) -> Option<RgbaImage> {
+		self.load_detailed(id).ok()
+	}
+
+	fn load_detailed(&self, id: &str) -> anyhow::Result<RgbaImage> {
+		let path = self.find_texture_path(id).ok_or_else(|| McModelError::TextureNotFound {
+			id: id.to_string(),
+			searched: format!("{:?} with any of {TEXTURE_EXTENSIONS:?}", self.texture_path(id)),
+		})?;
+		load_texture_file(&path)
+	}
+
+	fn describe_location(&self, id: &str) -> Option<String> {
+		Some(describe_path(&self.find_texture_path(id).unwrap_or_else(|| self.texture_path(id))))
+	}
+
+	fn load_animation(&self, id: &str) -> anyhow::Result<Option<McTextureAnimation>> {
+		self.find_texture_path(id).map_or(Ok(None), |path| load_animation_file(&path))
+	}
+}
+
+/// Absolute where possible, relative paths don't say much once the working directory isn't known
+fn describe_path(path: &Path) -> String {
+	std::path::absolute(path).as_deref().unwrap_or(path).display().to_string()
+}
+
+fn with_appended_extension(path: &Path, extension: &str) -> PathBuf {
+	let mut path = path.as_os_str().to_owned();
+	path.push(".");
+	path.push(extension);
+	path.into()
+}
+
+fn find_with_extensions<'a>(path: &Path, extensions: impl IntoIterator<Item = &'a str>) -> Option<PathBuf> {
+	extensions.into_iter().map(|extension| with_appended_extension(path, extension)).find(|path| path.is_file())
+}
+
+/// Decodes by the file's contents where the format can be told from them, falling back on the extension (like for
+/// `tga` which has no signature)
+fn load_texture_file(path: &Path) -> anyhow::Result<RgbaImage> {
+	let reader = image::io::Reader::open(path).with_context(|| format!("unable to open {path:?}"))?;
+	let image = reader
+		.with_guessed_format()
+		.with_context(|| format!("unable to read {path:?}"))?
+		.decode()
+		.with_context(|| format!("unable to decode {path:?}"))?;
+	Ok(image.to_rgba8())
+}
+
+impl McModelFace {
+	pub fn is_emissive(&self) -> bool {
+		self.emissive.unwrap_or(false)
+	}
+
+	/// Counter-rotates this face by `angle` degrees clockwise (as seen in texture space), moving the selected `uv`
+	/// area along with the geometry so that the texture stays world-aligned
+	fn uv_locked(&self, angle: i16) -> McModelFace {
+		let uv = self.uv.expect("face uv is filled in before uvlock");
+		let [u0, v0, u1, v1] = uv;
+		let uv = match angle.rem_euclid(360) {
+			90 => [16.0 - v1, u0, 16.0 - v0, u1],
+			180 => [16.0 - u1, 16.0 - v1, 16.0 - u0, 16.0 - v0],
+			270 => [v0, 16.0 - u1, v1, 16.0 - u0],
+			_ => uv,
+		};
+		McModelFace {
+			uv: Some(uv),
+			// Both reduced first as any `i16` can come from the json, which the difference of may not fit in
+			rotation: (self.rotation.rem_euclid(360) - angle.rem_euclid(360)).rem_euclid(360),
+			..self.clone()
+		}
+	}
+}
+
+impl McModelElement {
+	/// Box from `from` to `to` without any faces or rotation yet
+	pub fn new(from: [f64; 3], to: [f64; 3]) -> McModelElement {
+		McModelElement { from, to, ..Default::default() }
+	}
+
+	fn default_shade() -> bool {
+		true
+	}
+
+	fn is_default_shade(shade: &bool) -> bool {
+		*shade
+	}
+
+	/// The face's `uv`, or when it's left out the same area vanilla picks, which is the element's own extent
+	/// projected onto the face so the texture lines up with the block grid
+	pub fn face_uv(&self, dir: McModelDirection, face: &McModelFace) -> [f64; 4] {
+		if let Some(uv) = face.uv {
+			return uv;
+		}
+		let ([x0, y0, z0], [x1, y1, z1]) = (self.from, self.to);
+		match dir {
+			McModelDirection::Down => [x0, 16.0 - z1, x1, 16.0 - z0],
+			McModelDirection::Up => [x0, z0, x1, z1],
+			McModelDirection::North => [16.0 - x1, 16.0 - y1, 16.0 - x0, 16.0 - y0],
+			McModelDirection::South => [x0, 16.0 - y1, x1, 16.0 - y0],
+			McModelDirection::West => [z0, 16.0 - y1, z1, 16.0 - y0],
+			McModelDirection::East => [16.0 - z1, 16.0 - y1, 16.0 - z0, 16.0 - y0],
+		}
+	}
+
+	/// Copy with `from` and `to` clamped into `range` on every axis
+	fn clamped(&self, range: &std::ops::RangeInclusive<f64>) -> McModelElement {
+		McModelElement {
+			from: self.from.map(|c| c.clamp(*range.start(), *range.end())),
+			to: self.to.map(|c| c.clamp(*range.start(), *range.end())),
+			..self.clone()
+		}
+	}
+
+	/// A full block with every face using a texture that can never be found, so it shows the not-found checkerboard
+	fn placeholder_cube() -> McModelElement {
+		let face = McModelFace { uv: Some([0.0, 0.0, 16.0, 16.0]), ..McModelFace::new("#") };
+		McModelElement {
+			faces: McModelFaces {
+				north: Some(face.clone()),
+				east: Some(face.clone()),
+				south: Some(face.clone()),
+				west: Some(face.clone()),
+				up: Some(face.clone()),
+				down: Some(face),
+			},
+			..McModelElement::new([0.0, 0.0, 0.0], [16.0, 16.0, 16.0])
+		}
+	}
+
+	/// Where the ray from `origin` along `dir` goes into the element's box through one of its faces, as the face, how
+	/// many `dir`s along the ray that is and where on the face like `RayHit::uv`.  The ray gets taken into the
+	/// element's own unrotated space, which keeps the distances along it the same as they're only a multiple of `dir`.
+	fn raycast(&self, origin: Vector3<f64>, dir: Vector3<f64>) -> Option<(McModelDirection, f64, [f64; 2])> {
+		let local = ElementTransform::new(self.rotation.as_ref()).to_matrix().invert()?;
+		let origin = local.transform_point(Point3::from_vec(origin)).to_vec();
+		let dir = local.transform_vector(dir);
+		let min = vec3(self.from[0].min(self.to[0]), self.from[1].min(self.to[1]), self.from[2].min(self.to[2]));
+		let max = vec3(self.from[0].max(self.to[0]), self.from[1].max(self.to[1]), self.from[2].max(self.to[2]));
+		// Slabs, the ray is in the box between the last of it crossing into one and the first of it leaving one
+		let (mut enter, mut exit, mut enter_axis) = (f64::NEG_INFINITY, f64::INFINITY, None);
+		for axis in 0..3 {
+			if dir[axis] == 0.0 {
+				if origin[axis] < min[axis] || origin[axis] > max[axis] {
+					return None;
+				}
+				continue;
+			}
+			let (near, far) = if dir[axis] > 0.0 { (min[axis], max[axis]) } else { (max[axis], min[axis]) };
+			let (near, far) = ((near - origin[axis]) / dir[axis], (far - origin[axis]) / dir[axis]);
+			if near > enter {
+				(enter, enter_axis) = (near, Some(axis));
+			}
+			exit = exit.min(far);
+		}
+		if enter > exit || enter < 0.0 {
+			return None;
+		}
+		let direction = match (enter_axis?, dir[enter_axis?] > 0.0) {
+			(0, true) => McModelDirection::West,
+			(0, false) => McModelDirection::East,
+			(1, true) => McModelDirection::Down,
+			(1, false) => McModelDirection::Up,
+			(_, true) => McModelDirection::North,
+			(_, false) => McModelDirection::South,
+		};
+		self.faces.iter().find(|(dir, _)| *dir == direction)?;
+		// Measured from the face's top left corner, which is wherever its u and v axes start out from on the box
+		let hit = origin + dir * enter;
+		let (u_axis, v_axis) = direction.get_uv_axes();
+		let across = |axis: Vector3<f64>| {
+			let (start, extent) = (min.dot(axis).min(max.dot(axis)), (max - min).dot(axis).abs());
+			if extent > 0.0 {
+				((hit.dot(axis) - start) / extent * 16.0).clamp(0.0, 16.0)
+			} else {
+				0.0
+			}
+		};
+		Some((direction, enter, [across(u_axis), across(v_axis)]))
+	}
+
+	pub fn faces_enabled(&self) -> usize {
+		self.faces.iter().count()
+	}
+
+	pub fn transformation(&self) -> Mat4 {
+		ElementTransform::new(self.rotation.as_ref()).to_matrix().cast::<f32>().expect("f64 fits in f32")
+	}
+
+	/// The element's box after its own rotation, as the min/max corners of the axis aligned box around it
+	pub fn bounding_box(&self) -> (Vec3, Vec3) {
+		let mut min = vec3(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+		let mut max = vec3(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+		for pos in self.corners() {
+			min = vec3(min.x.min(pos.x), min.y.min(pos.y), min.z.min(pos.z));
+			max = vec3(max.x.max(pos.x), max.y.max(pos.y), max.z.max(pos.z));
+		}
+		(min.cast::<f32>().expect("f64 fits in f32"), max.cast::<f32>().expect("f64 fits in f32"))
+	}
+
+	/// The eight corners of the element's box after its own rotation
+	fn corners(&self) -> [Vector3<f64>; 8] {
+		let transform = ElementTransform::new(self.rotation.as_ref());
+		std::array::from_fn(|corner| {
+			let pick = |axis: usize| if corner & (1 << axis) == 0 { self.from[axis] } else { self.to[axis] };
+			transform.pos(vec3(pick(0), pick(1), pick(2)))
+		})
+	}
+}
+
+impl McModelJson {
+	pub fn parse_json_model_slice(json_data: &[u8]) -> Result<McModelJson, McModelError> {
+		serde_json::from_slice(json_data).map_err(McModelError::Parse)
+	}
+
+	pub fn parse_json_model_from_reader(json_data: impl std::io::Read) -> Result<McModelJson, McModelError> {
+		serde_json::from_reader(json_data).map_err(McModelError::Parse)
+	}
+
+	/// Reads a model file, with its path in the error when it can't be opened or parsed
+	pub fn parse_json_model_file(path: impl AsRef<Path>) -> Result<McModelJson, McModelError> {
+		let path = path.as_ref();
+		let file = std::fs::File::open(path).map_err(|source| McModelError::Io {
+			path: path.to_path_buf(),
+			source,
+		})?;
+		serde_json::from_reader(std::io::BufReader::new(file)).map_err(|source| McModelError::ParseFile {
+			path: path.to_path_buf(),
+			source,
+		})
+	}
+
+	/// Takes a model out of json that's already been parsed, like an editor holding on to the raw tree
+	pub fn from_value(value: serde_json::Value) -> Result<McModelJson, McModelError> {
+		serde_json::from_value(value).map_err(McModelError::Parse)
+	}
+
+	/// The model as a json tree, to edit parts of it this crate doesn't model and read it back with `from_value`
+	pub fn to_value(&self) -> anyhow::Result<serde_json::Value> {
+		Ok(serde_json::to_value(self)?)
+	}
+
+	/// Like `parse_json_model_slice` but erroring on every key vanilla doesn't know at any level, with the path to
+	/// each, to catch typos like `textrue` when checking a pack.  Keys vanilla knows but this crate doesn't model
+	/// (like `light_emission`) are fine while modded ones (like `forge_data`) are not, so the lenient parse is the one
+	/// to load modded files with.
+	pub fn parse_json_model_strict(json_data: &[u8]) -> Result<McModelJson, McModelError> {
+		let value: serde_json::Value = serde_json::from_slice(json_data).map_err(McModelError::Parse)?;
+		let unknown = strict::unknown_model_keys(&value);
+		if !unknown.is_empty() {
+			return Err(McModelError::UnknownKeys(unknown));
+		}
+		serde_json::from_value(value).map_err(McModelError::Parse)
+	}
+
+	/// Merges the parent chain into this model, children override their parents' textures and display slots while
+	/// elements are only inherited when the child has none of its own.  The chain stops at `builtin/*` parents,
+	/// which are left as the resulting model's `parent` as they have no file to load.
+	pub fn resolve_parents(&self, models: &(impl ModelSource + ?Sized)) -> Result<McModelJson, McModelError> {
+		let mut resolved = self.clone();
+		let mut seen = Vec::new();
+		while let Some(parent) = resolved.parent.take() {
+			let location = McResourceLocation::parse(&parent);
+			if location.path.starts_with("builtin/") {
+				resolved.parent = Some(parent);
+				break;
+			}
+			if seen.contains(&location) {
+				return Err(McModelError::ParentCycle(location.to_string()));
+			}
+			let parent_model = models
+				.load_model(&parent)
+				.map_err(|error| McModelError::from_model_source(&parent, error))?;
+			resolved.merge_parent(parent_model);
+			seen.push(location);
+		}
+		Ok(resolved)
+	}
+
+	/// A standalone copy of the model that needs no other files, for caching or exporting: the parent chain merged in
+	/// through `loader` and every texture variable mapped straight to its texture rather than through `#` references,
+	/// variables that don't resolve to anything are dropped.  A `builtin/*` parent is kept as it changes how the model
+	/// is drawn and there's nothing to merge in from it.
+	pub fn flatten<S: ModelSource>(&self, loader: &mut ModelLoader<S>) -> Result<McModelJson, McModelError> {
+		let mut flattened = self.clone();
+		if let Some(parent) = flattened.parent.take() {
+			if McResourceLocation::parse(&parent).path.starts_with("builtin/") {
+				flattened.parent = Some(parent);
+			} else {
+				flattened.merge_parent(McModelJson::clone(&*loader.load(&parent)?));
+			}
+		}
+		flattened.textures = flattened.resolved_textures()?;
+		Ok(flattened)
+	}
+
+	fn merge_parent(&mut self, parent: McModelJson) {
+		self.parent = parent.parent;
+		self.gui_light = self.gui_light.or(parent.gui_light);
+		self.ambient_occlusion = self.ambient_occlusion.or(parent.ambient_occlusion);
+		self.display = match (self.display.take(), parent.display) {
+			(Some(mut display), Some(parent_display)) => {
+				display.merge_parent(parent_display);
+				Some(display)
+			}
+			(display, parent_display) => display.or(parent_display),
+		};
+		let mut textures = parent.textures;
+		textures.extend(std::mem::take(&mut self.textures));
+		self.textures = textures;
+		let mut extra = parent.extra;
+		extra.extend(std::mem::take(&mut self.extra));
+		self.extra = extra;
+		if self.elements.is_empty() {
+			self.elements = parent.elements;
+		}
+	}
+
+	/// The `gui_light` of the model, vanilla treats a missing one as `side`
+	pub fn gui_light(&self) -> McModelGuiLight {
+		self.gui_light.unwrap_or_default()
+	}
+
+	/// The model of the override the item's `properties` select, the last one whose predicates all hold like vanilla
+	/// picks it.  A property the item doesn't have (isn't in `properties`) fails every predicate on it.
+	pub fn select_override(&self, properties: &HashMap<String, f64>) -> Option<&str> {
+		self.overrides
+			.iter()
+			.rev()
+			.find(|model_override| {
+				model_override
+					.predicate
+					.iter()
+					.all(|(property, least)| properties.get(property).is_some_and(|value| value >= least))
+			})
+			.map(|model_override| model_override.model.as_str())
+	}
+
+	/// The modded custom model loader the model is for, its `loader` key or `forge_marker` when it's only marked as
+	/// being in Forge's format, see [`McModelError::UnsupportedLoader`]
+	pub fn custom_loader(&self) -> Option<&str> {
+		match self.extra.get("loader") {
+			Some(serde_json::Value::String(loader)) => Some(loader),
+			_ if self.extra.contains_key("forge_marker") => Some("forge_marker"),
+			_ => None,
+		}
+	}
+
+	/// The `builtin/*` parent that the model's parent chain ends at, like [`BUILTIN_GENERATED`], on a model with its
+	/// parents resolved; an unresolved model only has one if it's its direct parent
+	pub fn builtin_parent(&self) -> Option<String> {
+		let location = McResourceLocation::parse(self.parent.as_deref()?);
+		location.path.starts_with("builtin/").then_some(location.path)
+	}
+
+	/// The `ambientocclusion` of the model, vanilla treats a missing one as on
+	pub fn ambient_occlusion(&self) -> bool {
+		self.ambient_occlusion.unwrap_or(true)
+	}
+
+	/// Follows `#other` references between texture variables down to an actual texture, `None` if the chain ends at
+	/// a variable that isn't defined
+	pub fn resolve_texture(&self, tex_id: &str) -> Result<Option<&str>, McModelError> {
+		let mut seen = vec![tex_id];
+		let mut tex_id = tex_id;
+		while let Some(value) = self.textures.get(tex_id) {
+			let Some(next) = value.strip_prefix('#') else {
+				return Ok(Some(value));
+			};
+			if seen.contains(&next) {
+				let cycle = seen.iter().chain([&next]).map(|var| var.to_string()).collect();
+				return Err(McModelError::TextureCycle(cycle));
+			}
+			seen.push(next);
+			tex_id = next;
+		}
+		Ok(None)
+	}
+
+	/// The texture the break and step particles are taken from, resolved through `#` references like a face's.  Vanilla
+	/// declares it on nearly every model without any face using it, so it shouldn't count as an unused variable.
+	pub fn particle_texture(&self) -> Option<&str> {
+		self.resolve_texture(PARTICLE_TEXTURE).ok().flatten()
+	}
+
+	/// Texture variables that are used by the game itself rather than through a face's `#` reference
+	pub fn is_non_face_texture(tex_id: &str) -> bool {
+		tex_id == PARTICLE_TEXTURE
+	}
+
+	/// Every texture variable that resolves to an actual texture, through `#` references if need be
+	pub fn resolved_textures(&self) -> Result<IndexMap<String, String>, McModelError> {
+		let mut resolved = IndexMap::with_capacity(self.textures.len());
+		for tex_id in self.textures.keys() {
+			if let Some(texture) = self.resolve_texture(tex_id)? {
+				resolved.insert(tex_id.clone(), texture.to_string());
+			}
+		}
+		Ok(resolved)
+	}
+
+	/// Texture variables at least one face references, without the `#` and in the order the faces first use them.
+	/// Unlike `textures` it leaves out what parents defined for faces the model no longer has, and it keeps
+	/// references to variables that aren't defined anywhere.
+	pub fn used_textures(&self) -> IndexSet<String> {
+		self.elements
+			.iter()
+			.flat_map(|element| element.faces.iter())
+			.filter_map(|(_, face)| face.texture.strip_prefix('#'))
+			.map(str::to_string)
+			.collect()
+	}
+
+	/// The alpha weighted average color of each texture variable's texture, loaded relative to `base_path` like
+	/// `to_cpu_mesh` does, handy for map colors and particle tints.  Fully transparent pixels don't count at all and
+	/// the alpha is the average over the rest, textures that don't load or are entirely transparent are left out.
+	pub fn texture_average_colors(&self, base_path: &Path) -> anyhow::Result<HashMap<String, Srgba>> {
+		self.texture_average_colors_from_source(&FsTextureSource::new(base_path))
+	}
+
+	/// Same as `texture_average_colors` but with the textures coming from anywhere, not just the filesystem
+	pub fn texture_average_colors_from_source(
+		&self,
+		textures: &dyn TextureSource,
+	) -> anyhow::Result<HashMap<String, Srgba>> {
+		let mut by_location = HashMap::new();
+		let mut colors = HashMap::new();
+		for (tex_id, tex_path) in self.resolved_textures()? {
+			let average = *by_location
+				.entry(McResourceLocation::parse(&tex_path))
+				.or_insert_with(|| textures.load(&tex_path).as_ref().and_then(average_color));
+			if let Some(average) = average {
+				colors.insert(tex_id, average);
+			}
+		}
+		Ok(colors)
+	}
+
+	/// The color the `dir` face of element `element_idx` shows at `u`, `v`, loaded relative to `base_path` like
+	/// `to_cpu_mesh` does.  Those are 0-16 across the face from the corner its texture's top left is at without any
+	/// face `rotation`, even though that and the face's `uv` are then followed to find the texel.  `None` if there's no
+	/// such face or its texture doesn't load or has no pixels at all, rather than the fallback texture.
+	pub fn sample_face_color(
+		&self,
+		base_path: &Path,
+		element_idx: usize,
+		dir: McModelDirection,
+		u: f64,
+		v: f64,
+		options: &SampleOptions,
+	) -> Option<Srgba> {
+		self.sample_face_color_from_source(&FsTextureSource::new(base_path), element_idx, dir, u, v, options)
+	}
+
+	/// Same as `sample_face_color` but with the textures coming from anywhere, not just the filesystem
+	pub fn sample_face_color_from_source(
+		&self,
+		textures: &dyn TextureSource,
+		element_idx: usize,
+		dir: McModelDirection,
+		u: f64,
+		v: f64,
+		options: &SampleOptions,
+	) -> Option<Srgba> {
+		let element = self.elements.get(element_idx)?;
+		let (_, face) = element.faces.iter().find(|(face_dir, _)| *face_dir == dir)?;
+		let texture = self.resolve_texture(face.texture.strip_prefix('#')?).ok()??;
+		let image = textures.load(texture).filter(|image| image.width() > 0 && image.height() > 0)?;
+		// The face's rotation turns the texture clockwise, so the point on it is found by turning the other way
+		let (u, v) = (u.clamp(0.0, 16.0), v.clamp(0.0, 16.0));
+		let (s, t) = match face.rotation.rem_euclid(360) {
+			0 => (u, v),
+			90 => (v, 16.0 - u),
+			180 => (16.0 - u, 16.0 - v),
+			270 => (16.0 - v, u),
+			_ => return None,
+		};
+		let [u0, v0, u1, v1] = element.face_uv(dir, face);
+		let texel = |from: f64, to: f64, at: f64, size: u32| {
+			((from + (to - from) * at / 16.0) / 16.0 * size as f64)
+				.floor()
+				.clamp(0.0, size as f64 - 1.0) as u32
+		};
+		let [r, g, b, a] = image
+			.get_pixel(texel(u0, u1, s, image.width()), texel(v0, v1, t, image.height()))
+			.0;
+		let mut color = [r, g, b].map(|c| c as f32 / 255.0);
+		if let Some(tint) = options.tint.filter(|_| face.tintindex.is_some()) {
+			color = [
+				color[0] * tint.r as f32 / 255.0,
+				color[1] * tint.g as f32 / 255.0,
+				color[2] * tint.b as f32 / 255.0,
+			];
+		}
+		if options.shading && element.shade && !face.is_emissive() {
+			color = color.map(|c| c * dir.get_shading_mult());
+		}
+		let [r, g, b] = color.map(|c| (c * 255.0).round() as u8);
+		Some(Srgba::new(r, g, b, a))
+	}
+
+	/// Whether any texture the faces can use has translucent or fully transparent pixels, so whether the model needs
+	/// to be drawn with blending or cutout at all, loaded relative to `base_path` like `to_cpu_mesh` does.  Textures
+	/// that don't load don't count as they are drawn with the fallback texture instead.
+	pub fn has_transparency(&self, base_path: &Path) -> bool {
+		self.texture_alpha(base_path) != TextureAlpha::Opaque
+	}
+
+	/// Same as `has_transparency` but with the textures coming from anywhere, not just the filesystem
+	pub fn has_transparency_from_source(&self, textures: &dyn TextureSource) -> bool {
+		self.texture_alpha_from_source(textures) != TextureAlpha::Opaque
+	}
+
+	/// What the alpha of the textures the faces can use calls for, the most demanding of them all, loaded relative to
+	/// `base_path` like `to_cpu_mesh` does.  Textures that don't load count as opaque.
+	pub fn texture_alpha(&self, base_path: &Path) -> TextureAlpha {
+		self.texture_alpha_from_source(&FsTextureSource::new(base_path))
+	}
+
+	/// Same as `texture_alpha` but with the textures coming from anywhere, not just the filesystem
+	pub fn texture_alpha_from_source(&self, textures: &dyn TextureSource) -> TextureAlpha {
+		let mut by_location = HashMap::new();
+		self.resolved_textures()
+			.unwrap_or_default()
+			.into_iter()
+			.filter(|(tex_id, _)| !McModelJson::is_non_face_texture(tex_id))
+			.map(|(_, tex_path)| {
+				*by_location
+					.entry(McResourceLocation::parse(&tex_path))
+					.or_insert_with(|| {
+						textures
+							.load(&tex_path)
+							.map_or(TextureAlpha::Opaque, |image| TextureAlpha::of_image(&image))
+					})
+			})
+			.max()
+			.unwrap_or(TextureAlpha::Opaque)
+	}
+
+	/// Each element's box as min/max corners in the 0-16 model space (divide by 16 for blocks), rotated elements
+	/// getting the tightest axis aligned box around them, for a collision shape rather than something to render.
+	/// Nothing gets loaded for this and flat elements come out as flat boxes.
+	pub fn collision_boxes(&self) -> Vec<(Vec3, Vec3)> {
+		self.elements.iter().map(McModelElement::bounding_box).collect()
+	}
+
+	/// The nearest face the ray from `origin` going along `dir` (which doesn't need to be normalized) strikes, in the
+	/// 0-16 model space with each element's rotation followed.  Only the faces the element has can be struck and only
+	/// from the outside, the same as what's drawn with back faces culled, so rays starting inside an element don't hit
+	/// it at all and it's possible to look through an element that's missing the face towards the ray.
+	pub fn raycast(&self, origin: Vec3, dir: Vec3) -> Option<RayHit> {
+		let origin = origin.cast::<f64>().expect("f32 fits in f64");
+		let dir = dir.cast::<f64>().expect("f32 fits in f64");
+		if !dir.magnitude2().is_normal() {
+			return None;
+		}
+		self.elements
+			.iter()
+			.enumerate()
+			.filter_map(|(element_idx, element)| {
+				let (direction, t, uv) = element.raycast(origin, dir)?;
+				Some(RayHit { element_idx, direction, distance: (t * dir.magnitude()) as f32, uv })
+			})
+			.min_by(|a, b| a.distance.total_cmp(&b.distance))
+	}
+
+	/// Whether `point` in the 0-16 model space is inside or on the surface of any element, with their rotations
+	/// followed, regardless of which faces they have
+	pub fn contains_point(&self, point: Vec3) -> bool {
+		let point = point.cast::<f64>().expect("f32 fits in f64");
+		self.elements.iter().any(|element| {
+			let Some(local) = ElementTransform::new(element.rotation.as_ref()).to_matrix().invert() else {
+				return false;
+			};
+			let local = local.transform_point(Point3::from_vec(point));
+			(0..3).all(|axis| {
+				(element.from[axis].min(element.to[axis])..=element.from[axis].max(element.to[axis]))
+					.contains(&local[axis])
+			})
+		})
+	}
+
+	/// Min/max extents of all the elements in the 0-16 model space, `None` if there are no elements
+	pub fn bounding_box(&self) -> Option<(Vec3, Vec3)> {
+		self.elements.iter().map(McModelElement::bounding_box).reduce(|(min, max), (element_min, element_max)| {
+			(
+				vec3(min.x.min(element_min.x), min.y.min(element_min.y), min.z.min(element_min.z)),
+				vec3(max.x.max(element_max.x), max.y.max(element_max.y), max.z.max(element_max.z)),
+			)
+		})
+	}
+
+	/// Center and radius of a sphere around all the elements in the 0-16 model space, centered on the middle of
+	/// `bounding_box` and just reaching the furthest element corner, for keeping a camera at a distance that suits
+	/// the model's size.  Models without any elements get the sphere of the placeholder cube they're drawn as.
+	pub fn bounding_sphere(&self) -> (Vec3, f32) {
+		let Some((min, max)) = self.bounding_box() else {
+			return (vec3(8.0, 8.0, 8.0), 8.0 * 3f32.sqrt());
+		};
+		let center = (min + max) * 0.5;
+		let corners = self.elements.iter().flat_map(McModelElement::corners);
+		let radius = corners
+			.map(|corner| (corner.cast::<f32>().expect("f64 fits in f32") - center).magnitude())
+			.fold(0.0, f32::max);
+		(center, radius)
+	}
+
+	/// What building the mesh would produce at most, worked out from the faces alone without loading textures or
+	/// building anything, so absurdly large models can be turned away up front.  A model without any faces gets
+	/// counted as it is, not as the placeholder cube it would be drawn as.
+	pub fn estimate_mesh_stats(&self) -> MeshStats {
+		let faces = self.face_count();
+		MeshStats {
+			faces,
+			triangles: faces * 2,
+			// Every face is a quad, its corners only merge with other faces sharing the same normal and uv
+			max_vertices: faces * 4,
+		}
+	}
+
+	pub fn face_count(&self) -> usize {
+		self.elements.iter().map(McModelElement::faces_enabled).sum()
+	}
+
+	/// The mesh and its atlas texture with the textures loaded relative to `texture_base_path`, textures that fail to
+	/// load get the fallback and go unreported, `to_cpu_mesh_with_atlas` hands those back along with the warnings
+	pub fn to_cpu_mesh(&self, texture_base_path: &Path) -> Result<(CpuMesh, CpuTexture), McModelError> {
+		let McModelMesh { mesh, texture, .. } = self.to_cpu_mesh_with_atlas(texture_base_path)?;
+		Ok((mesh, texture))
+	}
+
+	/// Same as `to_cpu_mesh` but also hands back where each texture ended up on the atlas
+	pub fn to_cpu_mesh_with_atlas(&self, texture_base_path: &Path) -> Result<McModelMesh, McModelError> {
+		self.to_cpu_mesh_from_source(&FsTextureSource::new(texture_base_path))
+	}
+
+	/// Writes just the packed atlas to `atlas_png_out` and where each texture went on it to `manifest_json_out` as
+	/// `{"textures": {"all": [x, y, w, h]}, "atlas_size": [w, h]}`, for viewers that do their own rendering and only
+	/// need to remap the model's uvs onto the atlas. The textures that failed to load are handed back like
+	/// `McModelMesh::texture_errors`, those aren't in the manifest.
+	pub fn export_atlas_manifest(
+		&self,
+		texture_base_path: &Path,
+		atlas_png_out: &Path,
+		manifest_json_out: &Path,
+	) -> anyhow::Result<Vec<TextureLoadError>> {
+		let model_mesh = self.to_cpu_mesh_with_atlas(texture_base_path)?;
+		model_mesh
+			.atlas_image()?
+			.save_with_format(atlas_png_out, image::ImageFormat::Png)
+			.with_context(|| format!("unable to write atlas to {atlas_png_out:?}"))?;
+		let manifest = serde_json::json!({
+			"textures": model_mesh.atlas_mappings,
+			"atlas_size": [model_mesh.texture.width, model_mesh.texture.height],
+		});
+		std::fs::write(manifest_json_out, serde_json::to_vec_pretty(&manifest)?)
+			.with_context(|| format!("unable to write atlas manifest to {manifest_json_out:?}"))?;
+		Ok(model_mesh.texture_errors)
+	}
+
+	/// Same as `to_cpu_mesh_with_atlas` but with the textures coming from anywhere, not just the filesystem
+	pub fn to_cpu_mesh_from_source(&self, textures: &dyn TextureSource) -> Result<McModelMesh, McModelError> {
+		self.to_cpu_mesh_with_options(textures, &MeshOptions::default())
+	}
+
+	/// Same as `to_cpu_mesh_with_atlas` but with the textures handed over by id, like `minecraft:block/stone`,
+	/// instead of read from anywhere, those that aren't there get the fallback texture
+	pub fn to_cpu_mesh_with_textures(
+		&self,
+		textures: &HashMap<String, RgbaImage>,
+	) -> Result<McModelMesh, McModelError> {
+		self.to_cpu_mesh_from_source(textures)
+	}
+
+	/// Same as `to_cpu_mesh` but with a blockstate variant's rotation applied to the whole model,
+	/// `MeshOptions::rotation` of `to_cpu_mesh_with_options` is the same with the texture errors and warnings handed
+	/// back
+	pub fn to_cpu_mesh_rotated(
+		&self,
+		texture_base_path: &Path,
+		variant_rotation: &McBlockstateRotation,
+	) -> Result<(CpuMesh, CpuTexture), McModelError> {
+		let options = MeshOptions {
+			rotation: *variant_rotation,
+			..Default::default()
+		};
+		let McModelMesh { mesh, texture, .. } =
+			self.to_cpu_mesh_with_options(&FsTextureSource::new(texture_base_path), &options)?;
+		Ok((mesh, texture))
+	}
+
+	pub fn to_cpu_mesh_with_options(
+		&self,
+		textures: &dyn TextureSource,
+		options: &MeshOptions,
+	) -> Result<McModelMesh, McModelError> {
+		options.limits.check(self)?;
+		let padding = options.atlas_padding.unwrap_or(if options.mipmaps { MIPMAP_PADDING } else { 0 });
+		self.to_cpu_mesh_on_own_atlas(textures, options, &mut AtlasBuilder::with_padding(&options.fallback, padding)?)
+	}
+
+	/// Same as `to_cpu_mesh_with_options` but packing onto `atlas` after [`AtlasBuilder::reset`]ting it rather than
+	/// onto a new one, which saves allocating and zeroing an atlas image for every model when building a lot of them
+	/// one after the other.  The result is the same as with a new atlas, whatever was on `atlas` before is gone.
+	pub fn to_cpu_mesh_reusing(
+		&self,
+		textures: &dyn TextureSource,
+		options: &MeshOptions,
+		atlas: &mut AtlasBuilder,
+	) -> Result<McModelMesh, McModelError> {
+		options.limits.check(self)?;
+		atlas.reset(
+			&options.fallback,
+			options
+				.atlas_padding
+				.unwrap_or(if options.mipmaps { MIPMAP_PADDING } else { 0 }),
+		)?;
+		self.to_cpu_mesh_on_own_atlas(textures, options, atlas)
+	}
+
+	/// Packs the model's textures onto an `atlas` nothing else is using and builds the mesh on it
+	fn to_cpu_mesh_on_own_atlas(
+		&self,
+		textures: &dyn TextureSource,
+		options: &MeshOptions,
+		atlas: &mut AtlasBuilder,
+	) -> Result<McModelMesh, McModelError> {
+		atlas.add_model_textures_from_source(self, textures)?;
+		let model_atlas = atlas.model_atlas(self)?;
+		let mut warnings = Vec::new();
+		let (mesh, translucent_mesh) = self.build_mesh(&model_atlas, &atlas.image, options, &mut warnings)?;
+		let mut texture = atlas.to_cpu_texture();
+		texture.min_filter = options.filter.interpolation();
+		texture.mag_filter = options.filter.interpolation();
+		if options.mipmaps {
+			texture.mip_map_filter = Some(Interpolation::Linear);
+		}
+		Ok(McModelMesh {
+			mesh,
+			translucent_mesh,
+			texture,
+			atlas_mappings: model_atlas.layout(),
+			texture_errors: std::mem::take(&mut atlas.errors),
+			warnings,
+			animations: std::mem::take(&mut atlas.animations).into_values().collect(),
+		})
+	}
+
+	/// Builds the mesh against an atlas shared with other models, the model's textures need to have been added to it
+	/// already and any that weren't use the atlas's fallback texture, `options.fallback` is not used here and neither
+	/// is `options.filter` other than for the uv insets, set that on the finished atlas texture. Every face ends up in
+	/// the one mesh, `options.translucency_split` is ignored. The uvs are relative to the atlas's current size, which
+	/// grows as textures are added, so add every model's textures before building any of the meshes.  Texture load
+	/// failures are in [`AtlasBuilder::errors`], the mesh warnings go unreported.
+	pub fn to_cpu_mesh_on_atlas(&self, atlas: &AtlasBuilder, options: &MeshOptions) -> Result<CpuMesh, McModelError> {
+		let options = MeshOptions {
+			translucency_split: None,
+			..options.clone()
+		};
+		let (mesh, _) = self.build_mesh(&atlas.model_atlas(self)?, &atlas.image, &options, &mut Vec::new())?;
+		Ok(mesh)
+	}
+
+	/// The mesh and, when splitting by translucency, the translucent faces' mesh, `image` being the atlas image
+	fn build_mesh(
+		&self,
+		atlas: &ModelAtlas,
+		image: &RgbaImage,
+		options: &MeshOptions,
+		warnings: &mut Vec<String>,
+	) -> Result<(CpuMesh, Option<CpuMesh>), McModelError> {
+		let (vertices, translucent_vertices) = self.build_vertices(atlas, image, options, warnings)?;
+		let translucent_mesh = translucent_vertices
+			.map(|vertices| vertices.into_cpu_mesh(options.tangents))
+			.transpose()?;
+		Ok((vertices.into_cpu_mesh(options.tangents)?, translucent_mesh))
+	}
+
+	/// The faces as `build_mesh` puts them in the mesh, the translucent ones separately with
+	/// `options.translucency_split`
+	fn build_vertices(
+		&self,
+		atlas: &ModelAtlas,
+		image: &RgbaImage,
+		options: &MeshOptions,
+		warnings: &mut Vec<String>,
+	) -> Result<(MeshVertices, Option<MeshVertices>), McModelError> {
+		if let Some(loader) = self.custom_loader() {
+			return Err(McModelError::UnsupportedLoader(loader.to_string()));
+		}
+		options.limits.check(self)?;
+		let face_count = self.face_count();
+		// Neither of these have elements the game would use, even when a model gives them some
+		match self.builtin_parent().as_deref() {
+			Some(BUILTIN_GENERATED) => {
+				let translucent_vertices = options.translucency_split.map(|_| MeshVertices::with_capacity(0));
+				return Ok((self.item_vertices(atlas, image), translucent_vertices));
+			}
+			Some(BUILTIN_ENTITY) => return Err(McModelError::BuiltinEntityModel),
+			_ => {}
+		}
+		// Something visibly went wrong is better than a black window if there's nothing to show
+		let placeholder;
+		let elements = if face_count > 0 {
+			&self.elements
+		} else if options.strict {
+			return Err(McModelError::NoRenderableFaces);
+		} else {
+			placeholder = [McModelElement::placeholder_cube()];
+			&placeholder[..]
+		};
+		let clamped;
+		let elements = match options.coordinates {
+			CoordinateRange::PassThrough => elements,
+			CoordinateRange::ClampVanilla => {
+				clamped = elements
+					.iter()
+					.enumerate()
+					.map(|(element_idx, element)| {
+						let clamped = element.clamped(&VANILLA_COORDINATE_RANGE);
+						if clamped.from != element.from || clamped.to != element.to {
+							warnings.push(format!(
+								"element {element_idx} from {:?} to {:?} clamped to {:?} to {:?}",
+								element.from, element.to, clamped.from, clamped.to
+							));
+						}
+						clamped
+					})
+					.collect::<Vec<_>>();
+				&clamped[..]
+			}
+		};
+
+		let variant_rotation = &options.rotation;
+		let variant_mat = variant_rotation.transformation()?;
+		let variant_origin = vec3(8.0, 8.0, 8.0);
+
+		// Mesh building
+		let mut vertices = MeshVertices::with_capacity(self.elements.len() * 36);
+		let mut translucent_vertices = MeshVertices::with_capacity(0);
+		let mut push_pos = |pos: Vector3<f64>, normal: Vector3<f64>, u: f64, v: f64, color: Srgba, translucent: bool| {
+			let vertices = if translucent { &mut translucent_vertices } else { &mut vertices };
+			vertices.push_pos(pos, normal, u, v, color)
+		};
+
+		// Pulls the uvs in a bit from the texture's edges so neighbors on the atlas don't bleed in, linear filtering
+		// blends in half a texel around the sampled point so it needs that much to keep only the texture's own texels.
+		// That only covers the full size level though, with mipmaps it's the atlas padding that keeps the neighbors out
+		// of the lower levels and this inset just keeps the full size level crisp at the edges.
+		let inset = options.uv_inset.unwrap_or(match options.filter {
+			FilterMode::Nearest => 1.0 / 16.0,
+			FilterMode::Linear => 0.5,
+		});
+		let (atlas_width, atlas_height) = (image.width() as f64, image.height() as f64);
+		let (bleed_u, bleed_v) = (inset / atlas_width, inset / atlas_height);
+		let get_uv = |face: &McModelFace| -> Result<(bool, [f64; 4]), McModelError> {
+			let offset = atlas.face_rect(&face.texture);
+			let [u0, v0, u1, v1] = face.uv.expect("face uv is filled in by variant_face");
+			let [v0, v1] = [v1, v0];
+			// Tools like to write -90 or 360 just as well, they're the same quarter turns
+			let (flip, [u0, v0, u1, v1]) =
+				match face.rotation.rem_euclid(360) {
+					0 => (false, [u0, v0, u1, v1]),
 					90 => (true, [u0, v1, u1, v0]),
 					180 => (false, [u1, v1, u0, v0]),
 					270 => (true, [u1, v0, u0, v1]),
-					_ => anyhow::bail!("unsupported rotation: {}", face.rotation),
+					_ => return Err(McModelError::UnsupportedFaceRotation(face.rotation)),
 				};
-			// TODO: Hard coded to size 16 for now, that might be by MC design, or maybe it's calculated from somewhere?
-			let u0 = (offset.min.x as f64 + u0) / 2048.0;
-			let v0 = (offset.min.y as f64 + v0) / 2048.0;
-			let u1 = (offset.min.x as f64 + u1) / 2048.0;
-			let v1 = (offset.min.y as f64 + v1) / 2048.0;
+			// Face uvs are always in 0-16 model units however many pixels the texture really has, so high resolution
+			// textures just get stretched over the same range
+			let (scale_u, scale_v) = (offset.width() as f64 / 16.0, offset.height() as f64 / 16.0);
+			let u0 = (offset.min.x as f64 + u0 * scale_u) / atlas_width;
+			let v0 = (offset.min.y as f64 + v0 * scale_v) / atlas_height;
+			let u1 = (offset.min.x as f64 + u1 * scale_u) / atlas_width;
+			let v1 = (offset.min.y as f64 + v1 * scale_v) / atlas_height;
 			let [u0, u1] = if u0 < u1 {
-				[u0 + bleed, u1 - bleed]
+				[u0 + bleed_u, u1 - bleed_u]
 			} else {
-				[u0 - bleed, u1 + bleed]
+				[u0 - bleed_u, u1 + bleed_u]
 			};
 			let [v0, v1] = if v0 < v1 {
-				[v0 + bleed, v1 - bleed]
+				[v0 + bleed_v, v1 - bleed_v]
 			} else {
-				[v0 - bleed, v1 + bleed]
+				[v0 - bleed_v, v1 + bleed_v]
 			};
 			Ok((flip, [u0, v0, u1, v1]))
 		};
+		// Where on the atlas the first corner of a face's uv as written is, which `UvMode::Clamp` keeps in place
+		let uv_start = |face: &McModelFace| -> [f64; 2] {
+			let offset = atlas.face_rect(&face.texture);
+			let [u0, v0, _, _] = face.uv.expect("face uv is filled in by variant_face");
+			[
+				(offset.min.x as f64 + u0 * offset.width() as f64 / 16.0) / atlas_width,
+				(offset.min.y as f64 + v0 * offset.height() as f64 / 16.0) / atlas_height,
+			]
+		};
+		// Whether any texel of the area a face shows is below the split's alpha threshold
+		let is_translucent = |face: &McModelFace| -> bool {
+			let Some(threshold) = options.translucency_split else {
+				return false;
+			};
+			let rect = atlas.face_rect(&face.texture);
+			// Nothing to be translucent in an empty rectangle, and the texel ranges below need at least one texel
+			if rect.is_empty() {
+				return false;
+			}
+			let [u0, v0, u1, v1] = face.uv.expect("face uv is filled in by variant_face");
+			let (scale_u, scale_v) = (rect.width() as f64 / 16.0, rect.height() as f64 / 16.0);
+			let texels = |min: f64, max: f64, start: i32, end: i32, scale: f64| {
+				let from = (start as f64 + min * scale).floor().clamp(start as f64, end as f64 - 1.0) as u32;
+				let to = (start as f64 + max * scale).ceil().clamp(from as f64 + 1.0, end as f64) as u32;
+				from..to
+			};
+			let xs = texels(u0.min(u1), u0.max(u1), rect.min.x, rect.max.x, scale_u);
+			let ys = texels(v0.min(v1), v0.max(v1), rect.min.y, rect.max.y, scale_v);
+			ys.flat_map(|y| xs.clone().map(move |x| (x, y))).any(|(x, y)| image.get_pixel(x, y).0[3] < threshold)
+		};
+
+		// Boxes of the other elements that can occlude a face's corners, rotated elements just use their bounding box
+		let ao = options.ao && self.ambient_occlusion();
+		let occluders = if ao {
+			elements
+				.iter()
+				.map(McModelElement::bounding_box)
+				.map(|(min, max)| {
+					(
+						min.cast::<f64>().expect("f32 fits in f64"),
+						max.cast::<f64>().expect("f32 fits in f64"),
+					)
+				})
+				.collect()
+		} else {
+			Vec::new()
+		};
+		let occluded = |element_idx: usize, pos: Vector3<f64>| {
+			occluders.iter().enumerate().any(|(idx, (min, max))| {
+				idx != element_idx
+					&& (min.x < pos.x && pos.x < max.x)
+					&& (min.y < pos.y && pos.y < max.y)
+					&& (min.z < pos.z && pos.z < max.z)
+			})
+		};
+
+		// Which face each quad pushed is and whether it went in the translucent vertices
+		let mut faces = Vec::new();
+		for (element_idx, element) in elements.iter().enumerate() {
+			let element_transform = ElementTransform::new(element.rotation.as_ref());
+			let element_center = vec3(
+				element.from[0] + element.to[0],
+				element.from[1] + element.to[1],
+				element.from[2] + element.to[2],
+			) * 0.5;
+			// Same idea as MC's smooth lighting, just against the other elements rather than neighboring blocks: the
+			// two edge neighbors and the corner neighbor just outside the face at each vertex darken it
+			let ambient_occlusion = |pos: Vector3<f64>, dir: McModelDirection| -> f32 {
+				let (u_axis, v_axis) = dir.get_uv_axes();
+				let rel = pos - element_center;
+				let u_offset = u_axis * rel.dot(u_axis).signum() * 0.5;
+				let v_offset = v_axis * rel.dot(v_axis).signum() * 0.5;
+				let outside = pos + dir.get_normal().cast::<f64>().expect("f32 fits in f64") * 0.5;
+				let side_u = occluded(element_idx, element_transform.pos(outside + u_offset));
+				let side_v = occluded(element_idx, element_transform.pos(outside + v_offset));
+				let corner = occluded(element_idx, element_transform.pos(outside + u_offset + v_offset));
+				let level = if side_u && side_v { 0 } else { 3 - side_u as usize - side_v as usize - corner as usize };
+				[0.5, 0.65, 0.8, 1.0][level]
+			};
+			let mut push_pos = |x: f64,
+			                    y: f64,
+			                    z: f64,
+			                    u: f64,
+			                    v: f64,
+			                    (color, fullbright): (Srgba, bool),
+			                    dir: McModelDirection,
+			                    translucent: bool| {
+				let color = if ao && !fullbright {
+					options.color_space.darken(color, ambient_occlusion(vec3(x, y, z), dir))
+				} else {
+					color
+				};
+				let pos = element_transform.pos(vec3(x, y, z));
+				let pos = variant_mat.transform_vector(pos - variant_origin) + variant_origin;
+				let normal = variant_mat.transform_vector(
+					element_transform.normal(dir.get_normal().cast::<f64>().expect("f32 fits in f64")),
+				);
+				push_pos(pos, normal, u, v, color, translucent);
+			};
+			// Shading is by the direction a face ends up at while uvlock undoes however much the face got spun around,
+			// the color also says whether the face is fullbright so ambient occlusion leaves it alone
+			let variant_face = |dir: McModelDirection, face: &McModelFace| -> ((Srgba, bool), McModelFace) {
+				let face = McModelFace {
+					uv: Some(element.face_uv(dir, face)),
+					..face.clone()
+				};
+				let normal = variant_mat.transform_vector(dir.get_normal().cast::<f64>().expect("f32 fits in f64"));
+				let new_dir = McModelDirection::from_normal_f64(normal);
+				let color = if face.is_emissive() {
+					(Srgba::WHITE, true)
+				} else if options.flat_lighting || !element.shade {
+					(Srgba::WHITE, false)
+				} else {
+					(options.color_space.shading_srgba(new_dir.get_shading_mult()), false)
+				};
+				if !variant_rotation.uvlock {
+					return (color, face);
+				}
+				let u_axis = variant_mat.transform_vector(dir.get_uv_axes().0);
+				let (new_u_axis, new_v_axis) = new_dir.get_uv_axes();
+				let angle = if u_axis.dot(new_u_axis) > 0.5 {
+					0
+				} else if u_axis.dot(new_v_axis) > 0.5 {
+					90
+				} else if u_axis.dot(new_u_axis) < -0.5 {
+					180
+				} else {
+					270
+				};
+				(color, face.uv_locked(angle))
+			};
+
+			let (p0, p1) = {
+				let [x0, x1] = minmax(element.from[0], element.to[0]);
+				let [y0, y1] = minmax(element.from[1], element.to[1]);
+				let [z0, z1] = minmax(element.from[2], element.to[2]);
+				(vec3(x0, y0, z0), vec3(x1, y1, z1))
+			};
+			for (dir, face) in element.faces.iter() {
+				if let Some(cullface) = face.cullface {
+					let cullface = McModelDirection::from_normal_f64(
+						variant_mat.transform_vector(cullface.get_normal().cast::<f64>().expect("f32 fits in f64")),
+					);
+					if options.culled.contains(&cullface) {
+						continue;
+					}
+				}
+				let (color, face) = variant_face(dir, face);
+				let (rotate, [u0, v0, u1, v1]) = get_uv(&face)?;
+				let translucent = is_translucent(&face);
+				let uvs = if !rotate {
+					[[u1, v0], [u1, v1], [u0, v1], [u0, v0]]
+				} else {
+					[[u1, v0], [u0, v0], [u0, v1], [u1, v1]]
+				};
+				let corners = dir.quad_corners(p0, p1);
+				let uvs = if face.uvlock { element_transform.uv_locked(dir, &corners, uvs) } else { uvs };
+				// Back-face culling relies on every face winding counter-clockwise as seen from outside, flat
+				// elements have zero area sides which don't wind either way
+				debug_assert!(
+					(corners[1] - corners[0])
+						.cross(corners[2] - corners[0])
+						.dot(dir.get_normal().cast::<f64>().expect("f32 fits in f64"))
+						>= 0.0,
+					"{dir:?} face winds the wrong way"
+				);
+				let quads = if options.uv_mode == UvMode::Clamp && !face.uvlock {
+					clamped_quads(&face, rotate, corners, uvs, uv_start(&face))
+				} else {
+					vec![(corners, uvs)]
+				};
+				for (corners, uvs) in quads {
+					for corner in [0, 1, 2, 2, 3, 0] {
+						let (pos, [u, v]) = (corners[corner], uvs[corner]);
+						push_pos(pos.x, pos.y, pos.z, u, v, color, dir, translucent);
+					}
+					faces.push((MeshBufferFace { element_idx, direction: dir }, translucent));
+				}
+			}
+		}
+		for (face, translucent) in faces {
+			if translucent { &mut translucent_vertices } else { &mut vertices }.faces.push(face);
+		}
+		Ok((vertices, options.translucency_split.map(|_| translucent_vertices)))
+	}
+
+	/// The mesh as flat arrays in a fixed order rather than a `CpuMesh`, for renderers of one's own and tools that
+	/// need to tell which face is which, see [`MeshBuffers`] for the layout.  Built against an atlas shared with other
+	/// models the same as `to_cpu_mesh_on_atlas`, so the model's textures need to have been added to it already and the
+	/// same options are ignored and the warnings go unreported the same.
+	pub fn to_mesh_buffers(&self, atlas: &AtlasBuilder, options: &MeshOptions) -> Result<MeshBuffers, McModelError> {
+		let options = MeshOptions {
+			translucency_split: None,
+			..options.clone()
+		};
+		let (vertices, _) = self.build_vertices(&atlas.model_atlas(self)?, &atlas.image, &options, &mut Vec::new())?;
+		let datas = vertices.datas.keys().collect::<Vec<_>>();
+		// Sorting is stable, so within a direction the faces stay in element order
+		let mut quads = vertices.faces.iter().enumerate().collect::<Vec<_>>();
+		quads.sort_by_key(|(_, face)| face.direction as usize);
+		let mut buffers = MeshBuffers::default();
+		for (quad, face) in quads {
+			// Each quad went in as the two triangles 0 1 2 and 2 3 0 of its corners
+			let indices = &vertices.indices[quad * 6..quad * 6 + 6];
+			let base = buffers.vertex_count() as u32;
+			for corner in [indices[0], indices[1], indices[2], indices[4]] {
+				let data = datas[corner as usize];
+				buffers.positions.extend([data.x, data.y, data.z].map(|c| c as f32));
+				buffers.uvs.extend([data.u, data.v].map(|c| c as f32));
+				buffers
+					.colors
+					.extend([data.color.r, data.color.g, data.color.b, data.color.a].map(|c| c as f32 / 255.0));
+				buffers.normals.extend([data.nx, data.ny, data.nz].map(|c| c as f32));
+			}
+			buffers.indices.extend([0, 1, 2, 2, 3, 0].map(|corner| base + corner));
+			buffers.faces.push(*face);
+		}
+		Ok(buffers)
+	}
+
+	/// Builds the extruded sprite mesh of a `parent: item/generated` style model from its `layer0`..`layerN`
+	/// textures, each layer is a 1 unit thick slab with side quads wherever a pixel borders a transparent one
+	pub fn to_item_mesh(&self, texture_base_path: &Path) -> Result<(CpuMesh, CpuTexture), McModelError> {
+		self.to_item_mesh_from_source(&FsTextureSource::new(texture_base_path))
+	}
+
+	/// Same as `to_item_mesh` but with the textures coming from anywhere, not just the filesystem.  Layers that fail
+	/// to load get the fallback and go unreported, `to_cpu_mesh_with_options` builds `builtin/generated` models the
+	/// same with the texture errors handed back.
+	pub fn to_item_mesh_from_source(
+		&self,
+		textures: &dyn TextureSource,
+	) -> Result<(CpuMesh, CpuTexture), McModelError> {
+		let mut builder = AtlasBuilder::new(&FallbackTexture::default())?;
+		builder.add_model_textures_from_source(self, textures)?;
+		let atlas = builder.model_atlas(self)?;
+		let texture = builder.to_cpu_texture();
+		let cpu_mesh = self.item_vertices(&atlas, &builder.image).into_cpu_mesh(false)?;
+		Ok((cpu_mesh, texture))
+	}
+
+	/// The faces of `to_item_mesh`, each quad's `MeshBufferFace::element_idx` being the layer it's of
+	fn item_vertices(&self, atlas: &ModelAtlas, image: &RgbaImage) -> MeshVertices {
+		let (atlas_width, atlas_height) = (image.width() as f64, image.height() as f64);
+
+		let mut vertices = MeshVertices::with_capacity(self.textures.len() * 36);
+		// Corners go counter-clockwise as seen from outside, starting at the bottom left
+		let mut push_quad = |layer: usize, corners: [[f64; 3]; 4], uvs: [[f64; 2]; 4], dir: McModelDirection| {
+			let color = dir.get_shading_srgba();
+			for i in [0, 1, 2, 2, 3, 0] {
+				let [x, y, z] = corners[i];
+				let [u, v] = uvs[i];
+				let normal = dir.get_normal().cast::<f64>().expect("f32 fits in f64");
+				vertices.push_pos(vec3(x, y, z), normal, u / atlas_width, v / atlas_height, color);
+			}
+			vertices.faces.push(MeshBufferFace { element_idx: layer, direction: dir });
+		};
+
+		let bleed = 16.0f64.recip();
+		for layer in 0.. {
+			let tex_id = format!("layer{layer}");
+			if !self.textures.contains_key(&tex_id) {
+				break;
+			}
+			let rect = atlas.rect(&tex_id);
+			let (width, height) = (rect.width() as u32, rect.height() as u32);
+			let (tex_x, tex_y) = (rect.min.x as f64, rect.min.y as f64);
+			let opaque = |x: i64, y: i64| -> bool {
+				(0..width as i64).contains(&x)
+					&& (0..height as i64).contains(&y)
+					&& image.get_pixel(rect.min.x as u32 + x as u32, rect.min.y as u32 + y as u32).0[3] > 0
+			};
+			// Each layer sits a hair outside the previous one so they don't z-fight
+			let z0 = 7.5 - layer as f64 * 0.01;
+			let z1 = 8.5 + layer as f64 * 0.01;
+			let (px_width, px_height) = (16.0 / width as f64, 16.0 / height as f64);
+
+			let (u0, v0, u1, v1) = (
+				tex_x + bleed,
+				tex_y + bleed,
+				tex_x + width as f64 - bleed,
+				tex_y + height as f64 - bleed,
+			);
+			push_quad(
+				layer,
+				[[0.0, 0.0, z1], [16.0, 0.0, z1], [16.0, 16.0, z1], [0.0, 16.0, z1]],
+				[[u0, v1], [u1, v1], [u1, v0], [u0, v0]],
+				McModelDirection::South,
+			);
+			push_quad(
+				layer,
+				[[16.0, 0.0, z0], [0.0, 0.0, z0], [0.0, 16.0, z0], [16.0, 16.0, z0]],
+				[[u1, v1], [u0, v1], [u0, v0], [u1, v0]],
+				McModelDirection::North,
+			);
+
+			for py in 0..height as i64 {
+				for px in 0..width as i64 {
+					if !opaque(px, py) {
+						continue;
+					}
+					let x0 = px as f64 * px_width;
+					let x1 = x0 + px_width;
+					let y1 = 16.0 - py as f64 * px_height;
+					let y0 = y1 - px_height;
+					// The edges just smear the border pixel across the slab thickness
+					let uv = [tex_x + px as f64 + 0.5, tex_y + py as f64 + 0.5];
+					let uvs = [uv; 4];
+					if !opaque(px - 1, py) {
+						push_quad(
+							layer,
+							[[x0, y0, z0], [x0, y0, z1], [x0, y1, z1], [x0, y1, z0]],
+							uvs,
+							McModelDirection::West,
+						);
+					}
+					if !opaque(px + 1, py) {
+						push_quad(
+							layer,
+							[[x1, y0, z1], [x1, y0, z0], [x1, y1, z0], [x1, y1, z1]],
+							uvs,
+							McModelDirection::East,
+						);
+					}
+					if !opaque(px, py - 1) {
+						push_quad(
+							layer,
+							[[x0, y1, z1], [x1, y1, z1], [x1, y1, z0], [x0, y1, z0]],
+							uvs,
+							McModelDirection::Up,
+						);
+					}
+					if !opaque(px, py + 1) {
+						push_quad(
+							layer,
+							[[x0, y0, z0], [x1, y0, z0], [x1, y0, z1], [x0, y0, z1]],
+							uvs,
+							McModelDirection::Down,
+						);
+					}
+				}
+			}
+		}
+
+		vertices
+	}
+}
+
+/// An element's rotation broken apart the way MC applies it, rotate about the origin and then rescale
+struct ElementTransform {
+	rot: Matrix4<f64>,
+	scale: Vector3<f64>,
+	origin: Vector3<f64>,
+}
+
+impl ElementTransform {
+	fn new(rotation: Option<&McModelRotation>) -> ElementTransform {
+		let Some(rot) = rotation else {
+			return ElementTransform {
+				rot: Matrix4::identity(),
+				scale: vec3(1.0, 1.0, 1.0),
+				origin: vec3(0.0, 0.0, 0.0),
+			};
+		};
+		let (mat, axis) = match rot.axis {
+			McModelRotationAxis::X => (Matrix4::<f64>::from_angle_x(Deg(rot.angle)), vec3(1.0, 0.0, 0.0)),
+			McModelRotationAxis::Y => (Matrix4::<f64>::from_angle_y(Deg(rot.angle)), vec3(0.0, 1.0, 0.0)),
+			McModelRotationAxis::Z => (Matrix4::<f64>::from_angle_z(Deg(rot.angle)), vec3(0.0, 0.0, 1.0)),
+		};
+		let scale = if rot.rescale {
+			// Vanilla only has angles up to 45 degrees either way, which is as far as its `1 / cos` holds up.  Past
+			// that it goes by how far the element is from its nearest quarter turn instead, which is what lines the
+			// faces back up with the block, rather than blowing up towards 90 degrees.
+			let off_axis = (rot.angle + 45.0).rem_euclid(90.0) - 45.0;
+			let rescale = Deg(off_axis).cos().recip();
+			vec3(1.0, 1.0, 1.0) * rescale - axis * (rescale - 1.0)
+		} else {
+			vec3(1.0, 1.0, 1.0)
+		};
+		ElementTransform {
+			rot: mat,
+			scale,
+			origin: vec3(rot.origin[0], rot.origin[1], rot.origin[2]),
+		}
+	}
+
+	fn pos(&self, pos: Vector3<f64>) -> Vector3<f64> {
+		let pos = self.rot.transform_vector(pos - self.origin);
+		vec3(pos.x * self.scale.x, pos.y * self.scale.y, pos.z * self.scale.z) + self.origin
+	}
+
+	/// The uvs of a face's `corners` counter-rotated so the texture keeps lined up with the block's axes after the
+	/// rotation, which only changes anything for faces the rotation spins in place.  The rotated texture is scaled to
+	/// fit inside the face's `uv` area again, as there's nothing past it in the atlas to show in its corners.
+	fn uv_locked(&self, dir: McModelDirection, corners: &[Vector3<f64>; 4], uvs: [[f64; 2]; 4]) -> [[f64; 2]; 4] {
+		let normal = dir.get_normal().cast::<f64>().expect("f32 fits in f64");
+		if (self.normal(normal) - normal).magnitude2() > 1e-12 {
+			return uvs;
+		}
+		let (u_axis, v_axis) = dir.get_uv_axes();
+		let center = (corners[0] + corners[1] + corners[2] + corners[3]) * 0.25;
+		let on_face = |offset: Vector3<f64>| vec2(offset.dot(u_axis), offset.dot(v_axis));
+		let flat = corners.map(|corner| on_face(corner - center));
+		let turned = corners.map(|corner| on_face(self.pos(corner) - self.pos(center)));
+		// The uvs follow from where on the face a point is, worked out from two of its edges, which a face without any
+		// area doesn't have
+		let Some(inverse) = Matrix2::from_cols(flat[1] - flat[0], flat[3] - flat[0]).invert() else {
+			return uvs;
+		};
+		let uv = uvs.map(|[u, v]| vec2(u, v));
+		let to_uv = Matrix2::from_cols(uv[1] - uv[0], uv[3] - uv[0]) * inverse;
+		let uv_center = (uv[0] + uv[1] + uv[2] + uv[3]) * 0.25;
+		let extent = |points: &[Vector2<f64>; 4]| {
+			points.iter().fold(vec2(0.0f64, 0.0), |max, p| {
+				vec2(max.x.max(p.x.abs()), max.y.max(p.y.abs()))
+			})
+		};
+		let (flat_extent, turned_extent) = (extent(&flat), extent(&turned));
+		let scale = (flat_extent.x / turned_extent.x).min(flat_extent.y / turned_extent.y);
+		turned.map(|offset| {
+			let uv = uv_center + to_uv * (offset * scale);
+			[uv.x, uv.y]
+		})
+	}
+
+	fn normal(&self, normal: Vector3<f64>) -> Vector3<f64> {
+		let normal = self.rot.transform_vector(normal);
+		vec3(normal.x / self.scale.x, normal.y / self.scale.y, normal.z / self.scale.z).normalize()
+	}
+
+	fn to_matrix(&self) -> Matrix4<f64> {
+		Matrix4::from_translation(self.origin)
+			* Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+			* self.rot
+			* Matrix4::from_translation(-self.origin)
+	}
+}
+
+/// Packs textures onto a single atlas image, and can be fed the textures of any number of models so that a batch of
+/// them can share one atlas, each texture file only being packed once however many models or variables use it
+pub struct AtlasBuilder {
+	image: RgbaImage,
+	allocator: etagere::AtlasAllocator,
+	/// Trimmed down to the actual texture size as the allocator can hand out larger rectangles than requested, `None`
+	/// for textures that failed to load
+	locations: HashMap<McResourceLocation, Option<etagere::Rectangle>>,
+	err_tex: etagere::Rectangle,
+	errors: Vec<TextureLoadError>,
+	padding: u32,
+	/// Packed as their first frame, in the order they were added
+	animations: IndexMap<McResourceLocation, AtlasAnimation>,
+	/// Everything on `image` that's been drawn into, which is all that needs clearing again on a reset
+	dirty: etagere::Rectangle,
+}
+
+/// `tile` with its edge pixels repeated `padding` times around it
+fn padded(tile: &RgbaImage, padding: u32) -> RgbaImage {
+	let (width, height) = tile.dimensions();
+	RgbaImage::from_fn(width + padding * 2, height + padding * 2, |x, y| {
+		let x = (x as i32 - padding as i32).clamp(0, width as i32 - 1) as u32;
+		let y = (y as i32 - padding as i32).clamp(0, height as i32 - 1) as u32;
+		*tile.get_pixel(x, y)
+	})
+}
+
+/// Border around each texture on the atlas when mipmapping, which keeps neighbors apart down to the mip level where a
+/// texel covers this many atlas pixels
+pub const MIPMAP_PADDING: u32 = 8;
+
+/// Size the atlas starts out at on each side
+pub const INITIAL_ATLAS_SIZE: u32 = 2048;
+
+/// Size the atlas can grow to on each side, about as large a texture as any GPU still takes
+pub const MAX_ATLAS_SIZE: u32 = 8192;
+
+/// The most padding an atlas takes, which still leaves a single pixel's room for a texture on the largest atlas
+pub const MAX_ATLAS_PADDING: u32 = (MAX_ATLAS_SIZE - 1) / 2;
+
+impl AtlasBuilder {
+	/// A [`INITIAL_ATLAS_SIZE`] square atlas with `fallback` already packed for any texture that doesn't load, it
+	/// doubles in size whenever a texture doesn't fit anymore up to [`MAX_ATLAS_SIZE`]
+	pub fn new(fallback: &FallbackTexture) -> Result<AtlasBuilder, McModelError> {
+		Self::with_padding(fallback, 0)
+	}
+
+	/// Same as `new` but with every texture surrounded by `padding` pixels repeating its edges, so that filtering
+	/// and lower mip levels blend in more of the texture itself rather than whatever got packed next to it, fails
+	/// with [`McModelError::PaddingTooLarge`] past [`MAX_ATLAS_PADDING`]
+	pub fn with_padding(fallback: &FallbackTexture, padding: u32) -> Result<AtlasBuilder, McModelError> {
+		if padding > MAX_ATLAS_PADDING {
+			return Err(McModelError::PaddingTooLarge(padding));
+		}
+		let mut atlas = AtlasBuilder {
+			image: RgbaImage::new(INITIAL_ATLAS_SIZE, INITIAL_ATLAS_SIZE),
+			allocator: etagere::AtlasAllocator::new(etagere::size2(
+				INITIAL_ATLAS_SIZE as i32,
+				INITIAL_ATLAS_SIZE as i32,
+			)),
+			locations: HashMap::new(),
+			err_tex: etagere::Rectangle::zero(),
+			errors: Vec::new(),
+			padding,
+			animations: IndexMap::new(),
+			dirty: etagere::Rectangle::zero(),
+		};
+		atlas.pack_fallback(fallback)?;
+		Ok(atlas)
+	}
+
+	/// Empties the atlas back to how `with_padding` starts it out, keeping its image and allocator around to pack the
+	/// next textures into.  Only what was drawn into gets cleared, and an atlas that grew goes back to
+	/// [`INITIAL_ATLAS_SIZE`] so the uvs built on it come out the same as on a new one.
+	pub fn reset(&mut self, fallback: &FallbackTexture, padding: u32) -> Result<(), McModelError> {
+		if padding > MAX_ATLAS_PADDING {
+			return Err(McModelError::PaddingTooLarge(padding));
+		}
+		if self.image.width() == INITIAL_ATLAS_SIZE {
+			let stride = self.image.width() as usize * 4;
+			let (min, max) = (self.dirty.min, self.dirty.max);
+			let raw: &mut [u8] = &mut self.image;
+			for row in raw.chunks_exact_mut(stride).take(max.y as usize).skip(min.y as usize) {
+				row[min.x as usize * 4..max.x as usize * 4].fill(0);
+			}
+			self.allocator.clear();
+		} else {
+			self.image = RgbaImage::new(INITIAL_ATLAS_SIZE, INITIAL_ATLAS_SIZE);
+			self.allocator =
+				etagere::AtlasAllocator::new(etagere::size2(INITIAL_ATLAS_SIZE as i32, INITIAL_ATLAS_SIZE as i32));
+		}
+		self.dirty = etagere::Rectangle::zero();
+		self.locations.clear();
+		self.errors.clear();
+		self.animations.clear();
+		self.padding = padding;
+		self.pack_fallback(fallback)
+	}
+
+	fn pack_fallback(&mut self, fallback: &FallbackTexture) -> Result<(), McModelError> {
+		self.err_tex = self.pack_image("not-found", &fallback.to_image())?;
+		Ok(())
+	}
+
+	/// Where the image landed, trimmed to the image itself without the padding around it, `id` is only for the error
+	fn pack_image(&mut self, id: &str, tile: &RgbaImage) -> Result<etagere::Rectangle, McModelError> {
+		use etagere::*;
+		let padding = self.padding as i32;
+		let tile_size = size2(tile.width() as i32, tile.height() as i32);
+		let padded_size = tile_size + size2(padding * 2, padding * 2);
+		let max_tile = MAX_ATLAS_SIZE - self.padding * 2;
+		// etagere never hands out an empty rectangle, so without padding this would grow the atlas to its largest
+		if tile.width() == 0 || tile.height() == 0 {
+			return Err(McModelError::EmptyImage { id: id.to_string(), width: tile.width(), height: tile.height() });
+		}
+		if padded_size.width > MAX_ATLAS_SIZE as i32 || padded_size.height > MAX_ATLAS_SIZE as i32 {
+			return Err(McModelError::TextureTooLarge {
+				id: id.to_string(),
+				width: tile.width(),
+				height: tile.height(),
+				max: max_tile,
+			});
+		}
+		let mapping = loop {
+			if let Some(mapping) = self.allocator.allocate(padded_size) {
+				break mapping;
+			}
+			self.grow()?;
+		};
+		let min = mapping.rectangle.min + size2(padding, padding).to_vector();
+		self.dirty = if self.dirty.is_empty() { mapping.rectangle } else { self.dirty.union(&mapping.rectangle) };
+		// The allocation is always inside the atlas, so copying into it can't go out of bounds
+		let copied = if padding > 0 {
+			self.image.copy_from(
+				&padded(tile, self.padding),
+				mapping.rectangle.min.x as u32,
+				mapping.rectangle.min.y as u32,
+			)
+		} else {
+			self.image.copy_from(tile, min.x as u32, min.y as u32)
+		};
+		copied.expect("atlas allocations fit the atlas image");
+		Ok(Rectangle::new(min, min + tile_size.to_vector()))
+	}
 
-		for element in &self.elements {
-			let (rot, origin) = if let Some(rot) = &element.rotation {
-				let mat = match rot.axis {
-					McModelRotationAxis::X => Matrix4::<f64>::from_angle_x(Deg(rot.angle)),
-					McModelRotationAxis::Y => Matrix4::<f64>::from_angle_y(Deg(rot.angle)),
-					McModelRotationAxis::Z => Matrix4::<f64>::from_angle_z(Deg(rot.angle)),
-				};
-				let origin = vec3(rot.origin[0], rot.origin[1], rot.origin[2]);
-				(mat, origin)
-			} else {
-				(Matrix4::<f64>::identity(), vec3(0.0, 0.0, 0.0))
-			};
-			let mut push_pos = |x: f64, y: f64, z: f64, u: f64, v: f64, color: Srgba| {
-				let pos = rot.transform_vector(vec3(x, y, z) - origin) + origin;
-				push_pos(pos.x, pos.y, pos.z, u, v, color);
-			};
+	/// Doubles the atlas on both sides.  etagere can't grow its allocator, so the new one gets everything so far as a
+	/// single allocation in the same top left corner, whatever was still free in there is given up.
+	fn grow(&mut self) -> Result<(), McModelError> {
+		let size = self.image.width();
+		if size >= MAX_ATLAS_SIZE {
+			return Err(McModelError::AtlasFull(MAX_ATLAS_SIZE));
+		}
+		let new_size = (size * 2).min(MAX_ATLAS_SIZE);
+		let mut allocator = etagere::AtlasAllocator::new(etagere::size2(new_size as i32, new_size as i32));
+		// The first allocation of an empty allocator always goes in its top left corner
+		let old = allocator
+			.allocate(etagere::size2(size as i32, size as i32))
+			.expect("the old atlas fits in the grown one");
+		assert_eq!(
+			old.rectangle.min,
+			etagere::point2(0, 0),
+			"the grown atlas didn't keep the old one in its corner"
+		);
+		let mut image = RgbaImage::new(new_size, new_size);
+		image.copy_from(&self.image, 0, 0).expect("the old atlas fits in the grown one");
+		self.image = image;
+		self.allocator = allocator;
+		Ok(())
+	}
 
-			let (p0, p1) = {
-				let [x0, x1] = minmax(element.from[0], element.to[0]);
-				let [y0, y1] = minmax(element.from[1], element.to[1]);
-				let [z0, z1] = minmax(element.from[2], element.to[2]);
-				(vec3(x0, y0, z0), vec3(x1, y1, z1))
-			};
-			if let Some(face) = &element.faces.north {
-				let (rotate, [u0, v0, u1, v1]) = get_uv(face)?;
-				let color = McModelDirection::North.get_shading_srgba();
-				if !rotate {
-					push_pos(p0.x, p0.y, p0.z, u1, v0, color);
-					push_pos(p0.x, p1.y, p0.z, u1, v1, color);
-					push_pos(p1.x, p1.y, p0.z, u0, v1, color);
-					push_pos(p1.x, p1.y, p0.z, u0, v1, color);
-					push_pos(p1.x, p0.y, p0.z, u0, v0, color);
-					push_pos(p0.x, p0.y, p0.z, u1, v0, color);
-				} else {
-					push_pos(p0.x, p0.y, p0.z, u1, v0, color);
-					push_pos(p0.x, p1.y, p0.z, u0, v0, color);
-					push_pos(p1.x, p1.y, p0.z, u0, v1, color);
-					push_pos(p1.x, p1.y, p0.z, u0, v1, color);
-					push_pos(p1.x, p0.y, p0.z, u1, v1, color);
-					push_pos(p0.x, p0.y, p0.z, u1, v0, color);
-				}
+	/// Packs every texture the model references, loaded relative to `base_path` like `to_cpu_mesh` does
+	pub fn add_model_textures(&mut self, model: &McModelJson, base_path: &Path) -> Result<(), McModelError> {
+		self.add_model_textures_from_source(model, &FsTextureSource::new(base_path))
+	}
+
+	/// Same as `add_model_textures` but with the textures coming from anywhere, not just the filesystem
+	pub fn add_model_textures_from_source(
+		&mut self,
+		model: &McModelJson,
+		textures: &dyn TextureSource,
+	) -> Result<(), McModelError> {
+		// Packing by texture ID rather than file order keeps the atlas byte for byte the same for the same textures
+		let mut resolved = model.resolved_textures()?;
+		resolved.sort_keys();
+		// Variables no face uses aren't worth the space, except for models whose textures something other than their
+		// faces uses, like the `layerN` of generated items which are drawn that way even with elements inherited
+		if !model.elements.is_empty() && model.builtin_parent().as_deref() != Some(BUILTIN_GENERATED) {
+			let used = model.used_textures();
+			resolved.retain(|tex_id, _| used.contains(tex_id) || McModelJson::is_non_face_texture(tex_id));
+		}
+		// Texture variables very often share the same file, so those only get loaded and packed once
+		let mut pending: Vec<(&str, &str, McResourceLocation)> = Vec::with_capacity(resolved.len());
+		for (tex_id, tex_path) in &resolved {
+			let location = McResourceLocation::parse(tex_path);
+			if !self.locations.contains_key(&location) && !pending.iter().any(|(_, _, pending)| *pending == location) {
+				pending.push((tex_id, tex_path, location));
 			}
-			if let Some(face) = &element.faces.east {
-				let (rotate, [u0, v0, u1, v1]) = get_uv(face)?;
-				let color = McModelDirection::East.get_shading_srgba();
-				if !rotate {
-					push_pos(p1.x, p0.y, p0.z, u1, v0, color);
-					push_pos(p1.x, p1.y, p0.z, u1, v1, color);
-					push_pos(p1.x, p1.y, p1.z, u0, v1, color);
-					push_pos(p1.x, p1.y, p1.z, u0, v1, color);
-					push_pos(p1.x, p0.y, p1.z, u0, v0, color);
-					push_pos(p1.x, p0.y, p0.z, u1, v0, color);
-				} else {
-					push_pos(p1.x, p0.y, p0.z, u1, v0, color);
-					push_pos(p1.x, p1.y, p0.z, u0, v0, color);
-					push_pos(p1.x, p1.y, p1.z, u0, v1, color);
-					push_pos(p1.x, p1.y, p1.z, u0, v1, color);
-					push_pos(p1.x, p0.y, p1.z, u1, v1, color);
-					push_pos(p1.x, p0.y, p0.z, u1, v0, color);
-				}
+		}
+		// Loading and decoding is what takes the time so that can go in parallel, the packing after stays in order
+		let load = |tex_path: &str| -> anyhow::Result<(RgbaImage, Option<McTextureAnimation>)> {
+			if tex_path.trim().is_empty() {
+				return Err(McModelError::EmptyTexture.into());
 			}
-			if let Some(face) = &element.faces.south {
-				let (rotate, [u0, v0, u1, v1]) = get_uv(face)?;
-				let color = McModelDirection::South.get_shading_srgba();
-				if !rotate {
-					push_pos(p1.x, p0.y, p1.z, u1, v0, color);
-					push_pos(p1.x, p1.y, p1.z, u1, v1, color);
-					push_pos(p0.x, p1.y, p1.z, u0, v1, color);
-					push_pos(p0.x, p1.y, p1.z, u0, v1, color);
-					push_pos(p0.x, p0.y, p1.z, u0, v0, color);
-					push_pos(p1.x, p0.y, p1.z, u1, v0, color);
-				} else {
-					push_pos(p1.x, p0.y, p1.z, u1, v0, color);
-					push_pos(p1.x, p1.y, p1.z, u0, v0, color);
-					push_pos(p0.x, p1.y, p1.z, u0, v1, color);
-					push_pos(p0.x, p1.y, p1.z, u0, v1, color);
-					push_pos(p0.x, p0.y, p1.z, u1, v1, color);
-					push_pos(p1.x, p0.y, p1.z, u1, v0, color);
-				}
+			let image = textures.load_detailed(tex_path)?;
+			// Reported like any other texture that can't be had so faces showing it get the fallback
+			if image.width() == 0 || image.height() == 0 {
+				let id = McResourceLocation::parse(tex_path).to_string();
+				return Err(McModelError::EmptyImage { id, width: image.width(), height: image.height() }.into());
 			}
-			if let Some(face) = &element.faces.west {
-				let (rotate, [u0, v0, u1, v1]) = get_uv(face)?;
-				let color = McModelDirection::West.get_shading_srgba();
-				if !rotate {
-					push_pos(p0.x, p0.y, p1.z, u1, v0, color);
-					push_pos(p0.x, p1.y, p1.z, u1, v1, color);
-					push_pos(p0.x, p1.y, p0.z, u0, v1, color);
-					push_pos(p0.x, p1.y, p0.z, u0, v1, color);
-					push_pos(p0.x, p0.y, p0.z, u0, v0, color);
-					push_pos(p0.x, p0.y, p1.z, u1, v0, color);
-				} else {
-					push_pos(p0.x, p0.y, p1.z, u1, v0, color);
-					push_pos(p0.x, p1.y, p1.z, u0, v0, color);
-					push_pos(p0.x, p1.y, p0.z, u0, v1, color);
-					push_pos(p0.x, p1.y, p0.z, u0, v1, color);
-					push_pos(p0.x, p0.y, p0.z, u1, v1, color);
-					push_pos(p0.x, p0.y, p1.z, u1, v0, color);
-				}
+			Ok((image, textures.load_animation(tex_path)?))
+		};
+		#[cfg(feature = "rayon")]
+		let loaded: Vec<_> = {
+			use rayon::prelude::*;
+			pending.par_iter().map(|(_, tex_path, _)| load(tex_path)).collect()
+		};
+		#[cfg(not(feature = "rayon"))]
+		let loaded: Vec<_> = pending.iter().map(|(_, tex_path, _)| load(tex_path)).collect();
+		for ((tex_id, tex_path, location), image) in pending.into_iter().zip(loaded) {
+			self.store_texture(tex_id, tex_path, location, image)?;
+		}
+		// Faces that don't get to any texture are drawn with the fallback just the same, so they're reported alongside
+		let mut unresolved: Vec<&str> = Vec::new();
+		for (_, face) in model.elements.iter().flat_map(|element| element.faces.iter()) {
+			let (id, error) = match face.texture.strip_prefix('#') {
+				Some(var) if resolved.contains_key(var) => continue,
+				Some(var) => (var, McModelError::UndefinedTextureVariable(var.to_string())),
+				None => (face.texture.as_str(), McModelError::NotATextureVariable(face.texture.clone())),
+			};
+			if !unresolved.contains(&face.texture.as_str()) {
+				unresolved.push(&face.texture);
+				self.errors.push(TextureLoadError {
+					id: id.to_string(),
+					path: face.texture.clone(),
+					source: Arc::new(error),
+				});
 			}
-			if let Some(face) = &element.faces.up {
-				let (rotate, [u0, v0, u1, v1]) = get_uv(face)?;
-				let color = McModelDirection::Up.get_shading_srgba();
-				if !rotate {
-					push_pos(p1.x, p1.y, p1.z, u1, v0, color);
-					push_pos(p1.x, p1.y, p0.z, u1, v1, color);
-					push_pos(p0.x, p1.y, p0.z, u0, v1, color);
-					push_pos(p0.x, p1.y, p0.z, u0, v1, color);
-					push_pos(p0.x, p1.y, p1.z, u0, v0, color);
-					push_pos(p1.x, p1.y, p1.z, u1, v0, color);
-				} else {
-					push_pos(p1.x, p1.y, p1.z, u1, v0, color);
-					push_pos(p1.x, p1.y, p0.z, u0, v0, color);
-					push_pos(p0.x, p1.y, p0.z, u0, v1, color);
-					push_pos(p0.x, p1.y, p0.z, u0, v1, color);
-					push_pos(p0.x, p1.y, p1.z, u1, v1, color);
-					push_pos(p1.x, p1.y, p1.z, u1, v0, color);
+		}
+		Ok(())
+	}
+
+	fn store_texture(
+		&mut self,
+		tex_id: &str,
+		tex_path: &str,
+		location: McResourceLocation,
+		loaded: anyhow::Result<(RgbaImage, Option<McTextureAnimation>)>,
+	) -> Result<(), McModelError> {
+		match loaded {
+			Ok((tile, animation)) => {
+				let animation = animation.and_then(|animation| AtlasAnimation::new(&tile, &animation));
+				let tile = animation.as_ref().map_or(&tile, AtlasAnimation::first_frame);
+				let rect = self.pack_image(&location.to_string(), tile)?;
+				if let Some(mut animation) = animation {
+					animation.rect = [rect.min.x as u32, rect.min.y as u32, rect.width() as u32, rect.height() as u32];
+					animation.padding = self.padding;
+					self.animations.insert(location.clone(), animation);
 				}
+				self.locations.insert(location, Some(rect));
 			}
-			if let Some(face) = &element.faces.down {
-				let (rotate, [u0, v0, u1, v1]) = get_uv(face)?;
-				let color = McModelDirection::Down.get_shading_srgba();
-				if !rotate {
-					push_pos(p1.x, p0.y, p0.z, u1, v0, color);
-					push_pos(p1.x, p0.y, p1.z, u1, v1, color);
-					push_pos(p0.x, p0.y, p1.z, u0, v1, color);
-					push_pos(p0.x, p0.y, p1.z, u0, v1, color);
-					push_pos(p0.x, p0.y, p0.z, u0, v0, color);
-					push_pos(p1.x, p0.y, p0.z, u1, v0, color);
-				} else {
-					push_pos(p1.x, p0.y, p0.z, u1, v0, color);
-					push_pos(p1.x, p0.y, p1.z, u0, v0, color);
-					push_pos(p0.x, p0.y, p1.z, u0, v1, color);
-					push_pos(p0.x, p0.y, p1.z, u0, v1, color);
-					push_pos(p0.x, p0.y, p0.z, u1, v1, color);
-					push_pos(p1.x, p0.y, p0.z, u1, v0, color);
-				}
+			Err(error) => {
+				self.errors.push(TextureLoadError {
+					id: tex_id.to_string(),
+					path: tex_path.to_string(),
+					source: Arc::from(Box::<dyn std::error::Error + Send + Sync>::from(error)),
+				});
+				self.locations.insert(location, None);
+			}
+		}
+		Ok(())
+	}
+
+	/// Every texture that failed to load so far and every face reference that doesn't resolve to one, those are drawn
+	/// with the fallback texture
+	pub fn errors(&self) -> &[TextureLoadError] {
+		&self.errors
+	}
+
+	/// The animated textures added so far, which are on the atlas at their first frame
+	pub fn animations(&self) -> impl Iterator<Item = &AtlasAnimation> {
+		self.animations.values()
+	}
+
+	/// The `[x, y, width, height]` rectangle a texture such as `minecraft:block/stone` was packed into, `None` if it
+	/// was never added or failed to load
+	pub fn mapping(&self, location: &str) -> Option<[u32; 4]> {
+		let rect = (*self.locations.get(&McResourceLocation::parse(location))?)?;
+		Some([rect.min.x as u32, rect.min.y as u32, rect.width() as u32, rect.height() as u32])
+	}
+
+	/// Texture ID to the rectangle it was packed into for one model, like `McModelMesh::atlas_mappings`
+	pub fn model_mappings(&self, model: &McModelJson) -> Result<IndexMap<String, [u32; 4]>, McModelError> {
+		Ok(self.model_atlas(model)?.layout())
+	}
+
+	/// The finished atlas texture, meshes built with `McModelJson::to_cpu_mesh_on_atlas` sample from it
+	pub fn finish(self) -> CpuTexture {
+		self.to_cpu_texture()
+	}
+
+	/// Where each of a model's texture variables landed on the atlas
+	fn model_atlas(&self, model: &McModelJson) -> Result<ModelAtlas, McModelError> {
+		let mut mappings = HashMap::with_capacity(model.textures.len());
+		for (tex_id, tex_path) in model.resolved_textures()? {
+			if let Some(Some(rect)) = self.locations.get(&McResourceLocation::parse(&tex_path)) {
+				mappings.insert(tex_id, *rect);
 			}
 		}
+		Ok(ModelAtlas { err_tex: self.err_tex, mappings })
+	}
+
+	fn to_cpu_texture(&self) -> CpuTexture {
+		CpuTexture {
+			name: "atlas".to_string(),
+			data: TextureData::RgbaU8(
+				self.image
+					.as_raw()
+					.chunks_exact(4)
+					.map(|texel| [texel[0], texel[1], texel[2], texel[3]])
+					.collect(),
+			),
+			width: self.image.width(),
+			height: self.image.height(),
+			min_filter: Interpolation::Nearest,
+			mag_filter: Interpolation::Nearest,
+			mip_map_filter: None,
+			wrap_s: Wrapping::ClampToEdge,
+			wrap_t: Wrapping::ClampToEdge,
+		}
+	}
+}
+
+/// A model's texture variables looked up on an atlas
+struct ModelAtlas {
+	mappings: HashMap<String, etagere::Rectangle>,
+	err_tex: etagere::Rectangle,
+}
+
+impl ModelAtlas {
+	/// Where a face's `#variable` texture landed on the atlas, the not-found checkerboard for anything that isn't one
+	fn face_rect(&self, texture: &str) -> etagere::Rectangle {
+		texture.strip_prefix('#').map_or(self.err_tex, |tex_id| self.rect(tex_id))
+	}
+
+	/// Where the texture ID landed on the atlas, or the not-found checkerboard if it never loaded
+	fn rect(&self, tex_id: &str) -> etagere::Rectangle {
+		if let Some(atlas_mapping) = self.mappings.get(tex_id) {
+			*atlas_mapping
+		} else {
+			self.err_tex
+		}
+	}
+
+	/// Sorted by texture ID so it comes out the same every time
+	fn layout(&self) -> IndexMap<String, [u32; 4]> {
+		let mut layout = self
+			.mappings
+			.iter()
+			.map(|(tex_id, rect)| {
+				(tex_id.clone(), [rect.min.x as u32, rect.min.y as u32, rect.width() as u32, rect.height() as u32])
+			})
+			.collect::<IndexMap<_, _>>();
+		layout.sort_keys();
+		layout
+	}
+}
+
+// Don't normally do this with floats unless you understand the dangers involved
+#[derive(PartialEq)]
+struct Vec3S {
+	x: f64,
+	y: f64,
+	z: f64,
+	nx: f64,
+	ny: f64,
+	nz: f64,
+	u: f64,
+	v: f64,
+	color: Srgba,
+}
+
+impl Eq for Vec3S {}
+
+impl Hash for Vec3S {
+	fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+		self.x.to_bits().hash(state);
+		self.y.to_bits().hash(state);
+		self.z.to_bits().hash(state);
+		self.nx.to_bits().hash(state);
+		self.ny.to_bits().hash(state);
+		self.nz.to_bits().hash(state);
+		self.u.to_bits().hash(state);
+		self.v.to_bits().hash(state);
+		self.color.hash(state);
+	}
+}
+
+/// Deduplicating vertex accumulator that the mesh builders push their triangles into
+struct MeshVertices {
+	datas: IndexMap<Vec3S, ()>,
+	indices: Vec<u32>,
+	/// The element face each six `indices` are, when built from a model's elements
+	faces: Vec<MeshBufferFace>,
+}
+
+impl MeshVertices {
+	fn with_capacity(capacity: usize) -> MeshVertices {
+		MeshVertices {
+			datas: IndexMap::with_capacity(capacity),
+			indices: Vec::with_capacity(capacity),
+			faces: Vec::new(),
+		}
+	}
+
+	/// Only vertices that also share their normal get merged, so faces keep their own flat normals
+	fn push_pos(&mut self, pos: Vector3<f64>, normal: Vector3<f64>, u: f64, v: f64, color: Srgba) {
+		let pos = Vec3S {
+			x: pos.x,
+			y: pos.y,
+			z: pos.z,
+			nx: normal.x,
+			ny: normal.y,
+			nz: normal.z,
+			u,
+			v,
+			color,
+		};
+		if let Some((idx, _, ())) = self.datas.get_full(&pos) {
+			self.indices.push(idx as u32);
+		} else {
+			self.indices.push(self.datas.len() as u32);
+			self.datas.insert(pos, ());
+		};
+	}
+
+	fn into_cpu_mesh(self, tangents: bool) -> Result<CpuMesh, McModelError> {
+		let MeshVertices { datas, indices, .. } = self;
 		let mut cpu_mesh = CpuMesh {
 			positions: Positions::F64(datas.keys().map(|d| vec3(d.x, d.y, d.z)).collect()),
 			indices: match datas.len() {
@@ -478,18 +3293,34 @@ impl McModelJson {
 				1..=255 => Indices::U8(indices.into_iter().map(|i| i as u8).collect()),
 				256..=65535 => Indices::U16(indices.into_iter().map(|i| i as u16).collect()),
 				65536..=4294967295 => Indices::U32(indices.into_iter().collect()), // Wtf huge?
-				_ => anyhow::bail!("too many indices: {}", datas.len()),
+				_ => return Err(McModelError::TooManyVertices(datas.len())),
 			},
-			normals: None,
+			normals: Some(datas.keys().map(|d| vec3(d.nx as f32, d.ny as f32, d.nz as f32)).collect()),
 			tangents: None,
 			uvs: Some(datas.keys().map(|d| vec2(d.u as f32, d.v as f32)).collect()),
 			colors: Some(datas.keys().map(|d| d.color).collect()),
 		};
-		cpu_mesh.compute_normals();
-		cpu_mesh.compute_tangents();
+		if tangents {
+			cpu_mesh.compute_tangents();
+		}
 		cpu_mesh.compute_aabb();
-		Ok((cpu_mesh, texture))
+		Ok(cpu_mesh)
+	}
+}
+
+fn average_color(image: &RgbaImage) -> Option<Srgba> {
+	let (mut sum, mut alpha, mut count) = ([0u64; 3], 0u64, 0u64);
+	for pixel in image.pixels().filter(|pixel| pixel.0[3] > 0) {
+		let [r, g, b, a] = pixel.0.map(u64::from);
+		sum = [sum[0] + r * a, sum[1] + g * a, sum[2] + b * a];
+		alpha += a;
+		count += 1;
 	}
+	if count == 0 {
+		return None;
+	}
+	let [r, g, b] = sum.map(|c| (c / alpha) as u8);
+	Some(Srgba::new(r, g, b, (alpha / count) as u8))
 }
 
 fn minmax(a: f64, b: f64) -> [f64; 2] {
@@ -499,3 +3330,837 @@ fn minmax(a: f64, b: f64) -> [f64; 2] {
 		[b, a]
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Solid `size` square textures by location, for models to be built against
+	fn textures(ids: &[&str], size: u32) -> HashMap<String, RgbaImage> {
+		let white = image::Rgba([255, 255, 255, 255]);
+		ids.iter().map(|id| (id.to_string(), RgbaImage::from_pixel(size, size, white))).collect()
+	}
+
+	/// The model's mesh buffers on an atlas of its own
+	fn buffers(model: &McModelJson, textures: &dyn TextureSource, options: &MeshOptions) -> MeshBuffers {
+		let mut atlas = AtlasBuilder::new(&options.fallback).unwrap();
+		atlas.add_model_textures_from_source(model, textures).unwrap();
+		model.to_mesh_buffers(&atlas, options).unwrap()
+	}
+
+	/// A quad's corners as `(position, uv, normal)`, positions rounded as the models in here only have whole ones
+	fn quad(buffers: &MeshBuffers, quad: usize) -> Vec<([i32; 3], [f32; 2], [f32; 3])> {
+		(quad * 4..quad * 4 + 4)
+			.map(|vertex| {
+				let position = [0, 1, 2].map(|axis| buffers.positions[vertex * 3 + axis].round() as i32);
+				let uv = [buffers.uvs[vertex * 2], buffers.uvs[vertex * 2 + 1]];
+				let normal = [0, 1, 2].map(|axis| buffers.normals[vertex * 3 + axis]);
+				(position, uv, normal)
+			})
+			.collect()
+	}
+
+	/// The quad on the `direction` side of the model as it was written
+	fn face_quad(buffers: &MeshBuffers, direction: McModelDirection) -> Vec<([i32; 3], [f32; 2], [f32; 3])> {
+		let index = buffers.faces.iter().position(|face| face.direction == direction).unwrap();
+		quad(buffers, index)
+	}
+
+	fn uv_at(corners: &[([i32; 3], [f32; 2], [f32; 3])], position: [i32; 3]) -> [f32; 2] {
+		corners.iter().find(|(pos, _, _)| *pos == position).unwrap().1
+	}
+
+	/// A full block with only a top and a north face, uvs by where they are on the block
+	fn top_and_north_cube() -> McModelJson {
+		McModelBuilder::new()
+			.texture("all", "block/stone")
+			.element([0.0; 3], [16.0; 3])
+			.face(McModelDirection::Up, "#all", None)
+			.face(McModelDirection::North, "#all", None)
+			.build()
+	}
+
+	fn rotated(y: i16, uvlock: bool) -> MeshOptions {
+		MeshOptions { rotation: McBlockstateRotation { x: 0, y, uvlock }, uv_inset: Some(0.0), ..Default::default() }
+	}
+
+	const DIRECTIONS: [McModelDirection; 6] = [
+		McModelDirection::North,
+		McModelDirection::East,
+		McModelDirection::South,
+		McModelDirection::West,
+		McModelDirection::Up,
+		McModelDirection::Down,
+	];
+
+	/// `block/cube_all` flattened by hand, every side showing `#all`
+	fn full_cube() -> McModelBuilder {
+		DIRECTIONS
+			.into_iter()
+			.fold(McModelBuilder::new().texture("all", "block/stone").element([0.0; 3], [16.0; 3]), |builder, dir| {
+				builder.face(dir, "#all", None)
+			})
+	}
+
+	/// Where a point of the model ends up with the blockstate rotation
+	fn rotate(rotation: &McBlockstateRotation, [x, y, z]: [i32; 3]) -> [i32; 3] {
+		let origin = vec3(8.0, 8.0, 8.0);
+		let pos = vec3(x as f64, y as f64, z as f64) - origin;
+		let pos = rotation.transformation().unwrap().transform_vector(pos) + origin;
+		[pos.x, pos.y, pos.z].map(|c| c.round() as i32)
+	}
+
+	#[test]
+	fn y_rotation_turns_geometry_and_textures_with_it() {
+		let (model, textures) = (top_and_north_cube(), textures(&["block/stone"], 16));
+		let plain = buffers(&model, &textures, &rotated(0, false));
+		let options = rotated(90, false);
+		let turned = buffers(&model, &textures, &options);
+
+		// A quarter turn clockwise seen from above takes north to east
+		let north = face_quad(&turned, McModelDirection::North);
+		assert!(north.iter().all(|(pos, _, normal)| pos[0] == 16 && *normal == [1.0, 0.0, 0.0]));
+		assert!(face_quad(&turned, McModelDirection::Up).iter().all(|(_, _, normal)| *normal == [0.0, 1.0, 0.0]));
+
+		// Without uvlock every corner takes its uv along to where it got turned to
+		let plain_up = face_quad(&plain, McModelDirection::Up);
+		let turned_up = face_quad(&turned, McModelDirection::Up);
+		for (pos, uv, _) in &plain_up {
+			assert_eq!(uv_at(&turned_up, rotate(&options.rotation, *pos)), *uv, "corner {pos:?}");
+		}
+		// Which means the texture isn't where it was in the world anymore
+		assert!(plain_up.iter().any(|(pos, uv, _)| uv_at(&turned_up, *pos) != *uv));
+	}
+
+	#[test]
+	fn y_rotation_with_uvlock_keeps_textures_world_aligned() {
+		let (model, textures) = (top_and_north_cube(), textures(&["block/stone"], 16));
+		let plain = buffers(&model, &textures, &rotated(0, false));
+		let turned = buffers(&model, &textures, &rotated(90, true));
+
+		// The geometry turns just the same
+		let north = face_quad(&turned, McModelDirection::North);
+		assert!(north.iter().all(|(pos, _, normal)| pos[0] == 16 && *normal == [1.0, 0.0, 0.0]));
+
+		// But the top's uvs stay where they were in the world, the same corner of the texture on the same corner
+		let plain_up = face_quad(&plain, McModelDirection::Up);
+		let turned_up = face_quad(&turned, McModelDirection::Up);
+		for (pos, uv, _) in &plain_up {
+			assert_eq!(uv_at(&turned_up, *pos), *uv, "corner {pos:?}");
+		}
+	}
+
+	#[test]
+	fn generated_items_keep_their_layers_with_elements_inherited() {
+		let model = McModelBuilder::new()
+			.parent(BUILTIN_GENERATED)
+			.texture("layer0", "item/stick")
+			.texture("side", "block/stone")
+			.element([0.0; 3], [16.0; 3])
+			.face(McModelDirection::Up, "#side", None)
+			.build();
+		let mut atlas = AtlasBuilder::new(&FallbackTexture::default()).unwrap();
+		atlas.add_model_textures_from_source(&model, &textures(&["item/stick", "block/stone"], 16)).unwrap();
+		assert!(atlas.mapping("item/stick").is_some());
+		let mesh = model.to_cpu_mesh_from_source(&textures(&["item/stick", "block/stone"], 16)).unwrap();
+		assert!(mesh.atlas_mappings.contains_key("layer0"));
+		assert!(mesh.texture_errors.is_empty());
+
+		// Without the generated parent the same unused variable is left off
+		let model = McModelJson { parent: None, ..model };
+		let mut atlas = AtlasBuilder::new(&FallbackTexture::default()).unwrap();
+		atlas.add_model_textures_from_source(&model, &textures(&["item/stick", "block/stone"], 16)).unwrap();
+		assert!(atlas.mapping("item/stick").is_none());
+		assert!(atlas.mapping("block/stone").is_some());
+	}
+
+	#[test]
+	fn cube_has_flat_normals_per_face() {
+		let mesh = full_cube().build().to_cpu_mesh_from_source(&textures(&["block/stone"], 16)).unwrap().mesh;
+		// Corners shared by three faces are three vertices with a normal each, not one with them averaged
+		assert_eq!(mesh.vertex_count(), 24);
+		assert_eq!(mesh.indices.len(), Some(36));
+		let positions = mesh.positions.to_f64();
+		let normals = mesh.normals.unwrap();
+		for dir in DIRECTIONS {
+			let normal = dir.get_normal();
+			let on_side: Vec<_> =
+				positions.iter().zip(&normals).filter(|(_, n)| **n == normal).map(|(pos, _)| *pos).collect();
+			assert_eq!(on_side.len(), 4, "{dir:?}");
+			// All of them out on that side of the block
+			let normal = normal.cast::<f64>().unwrap();
+			assert!(on_side.iter().all(|pos| (pos - vec3(8.0, 8.0, 8.0)).dot(normal) == 8.0), "{dir:?}");
+		}
+	}
+
+	#[test]
+	fn variables_sharing_a_texture_share_its_allocation() {
+		let model = McModelBuilder::new()
+			.texture("side", "block/stone")
+			.texture("top", "minecraft:block/stone")
+			.texture("all", "#side")
+			.element([0.0; 3], [16.0; 3])
+			.face(McModelDirection::North, "#side", None)
+			.face(McModelDirection::Up, "#top", None)
+			.face(McModelDirection::Down, "#all", None)
+			.build();
+		let mut atlas = AtlasBuilder::new(&FallbackTexture::default()).unwrap();
+		let fallback_space = atlas.allocator.allocated_space();
+		atlas.add_model_textures_from_source(&model, &textures(&["block/stone"], 16)).unwrap();
+		assert_eq!(atlas.allocator.allocated_space() - fallback_space, 16 * 16);
+
+		let mappings = atlas.model_mappings(&model).unwrap();
+		assert_eq!(mappings.len(), 3);
+		assert!(mappings.values().all(|rect| *rect == mappings["side"]));
+	}
+
+	#[test]
+	fn high_resolution_textures_map_over_the_same_uv_range() {
+		let model = McModelBuilder::new()
+			.texture("all", "block/big")
+			.element([0.0; 3], [16.0; 3])
+			.face(McModelDirection::Up, "#all", None)
+			.face(McModelDirection::North, "#all", [4.0, 4.0, 12.0, 12.0])
+			.build();
+		let textures = textures(&["block/big"], 32);
+		let options = MeshOptions { uv_inset: Some(0.0), ..Default::default() };
+		let mut atlas = AtlasBuilder::new(&options.fallback).unwrap();
+		atlas.add_model_textures_from_source(&model, &textures).unwrap();
+		let [x, y, width, height] = atlas.mapping("block/big").unwrap();
+		assert_eq!((width, height), (32, 32));
+		let buffers = model.to_mesh_buffers(&atlas, &options).unwrap();
+
+		// In atlas pixels, `[u0, v0, u1, v1]` over the quad's corners
+		let pixels = |dir| {
+			let corners = face_quad(&buffers, dir);
+			let us = corners.iter().map(|(_, [u, _], _)| (*u * INITIAL_ATLAS_SIZE as f32).round() as u32);
+			let vs = corners.iter().map(|(_, [_, v], _)| (*v * INITIAL_ATLAS_SIZE as f32).round() as u32);
+			[us.clone().min().unwrap(), vs.clone().min().unwrap(), us.max().unwrap(), vs.max().unwrap()]
+		};
+		// The whole 0-16 range is all 32 pixels, and 4-12 the middle 16 of them
+		assert_eq!(pixels(McModelDirection::Up), [x, y, x + 32, y + 32]);
+		assert_eq!(pixels(McModelDirection::North), [x + 8, y + 8, x + 24, y + 24]);
+	}
+
+	#[test]
+	fn same_model_packs_into_the_same_atlas() {
+		let ids = ["block/a", "block/b", "block/c", "block/d", "block/e"];
+		// Different sizes and colors so a different packing order would show, and a new `HashMap` each time so the
+		// order they're looked up in differs too
+		let textures = || -> HashMap<String, RgbaImage> {
+			ids.iter()
+				.enumerate()
+				.map(|(i, id)| {
+					let size = 16 << (i % 3);
+					let color = image::Rgba([i as u8 * 50, 255 - i as u8 * 50, 0, 255]);
+					(id.to_string(), RgbaImage::from_pixel(size, size, color))
+				})
+				.collect()
+		};
+		let model = |ids: &[&str]| {
+			let builder = ids.iter().fold(McModelBuilder::new(), |builder, id| builder.texture(&id[6..], *id));
+			let builder = builder.element([0.0; 3], [16.0; 3]);
+			let faces = ids.iter().zip(DIRECTIONS);
+			faces.fold(builder, |builder, (id, dir)| builder.face(dir, format!("#{}", &id[6..]), None)).build()
+		};
+		let build = |model: &McModelJson| model.to_cpu_mesh_from_source(&textures()).unwrap();
+		let first = build(&model(&ids));
+		// The same variables declared the other way around
+		let reversed: Vec<_> = ids.iter().rev().copied().collect();
+		let second = build(&model(&reversed));
+
+		let (TextureData::RgbaU8(first_texels), TextureData::RgbaU8(second_texels)) =
+			(&first.texture.data, &second.texture.data)
+		else {
+			panic!("the atlas is always rgba8");
+		};
+		assert!(first_texels == second_texels);
+		assert_eq!(first.atlas_mappings, second.atlas_mappings);
+		assert_eq!(first.atlas_mappings.len(), ids.len());
+	}
+
+	#[test]
+	fn auto_uvs_match_vanilla_for_a_non_cube_element() {
+		let model = McModelJson::parse_json_model_slice(
+			br##"{"textures": {"all": "block/stone"}, "elements": [{"from": [2, 3, 4], "to": [10, 7, 15], "faces": {
+				"down": {"texture": "#all"}, "up": {"texture": "#all"}, "north": {"texture": "#all"},
+				"south": {"texture": "#all"}, "west": {"texture": "#all"},
+				"east": {"texture": "#all", "uv": [0, 0, 1, 1]}
+			}}]}"##,
+		)
+		.unwrap();
+		let element = &model.elements[0];
+		// What vanilla's `BlockElement` works out for the same element, and a uv that's given staying as it is
+		let vanilla = [
+			(McModelDirection::North, [6.0, 9.0, 14.0, 13.0]),
+			(McModelDirection::East, [0.0, 0.0, 1.0, 1.0]),
+			(McModelDirection::South, [2.0, 9.0, 10.0, 13.0]),
+			(McModelDirection::West, [4.0, 9.0, 15.0, 13.0]),
+			(McModelDirection::Up, [2.0, 4.0, 10.0, 15.0]),
+			(McModelDirection::Down, [2.0, 1.0, 10.0, 12.0]),
+		];
+		for ((dir, face), (vanilla_dir, uv)) in element.faces.iter().zip(vanilla) {
+			assert_eq!(dir, vanilla_dir);
+			assert_eq!(element.face_uv(dir, face), uv, "{dir:?}");
+		}
+		assert!(element.faces.north.as_ref().unwrap().uv.is_none());
+	}
+
+	#[test]
+	fn every_face_winds_counter_clockwise_from_outside() {
+		let model = full_cube().build();
+		let textures = textures(&["block/stone"], 16);
+		for (x, y) in [(0, 0), (90, 0), (0, 90), (180, 270)] {
+			let options = MeshOptions { rotation: McBlockstateRotation { x, y, uvlock: false }, ..Default::default() };
+			let buffers = buffers(&model, &textures, &options);
+			let position = |vertex: u32| {
+				let vertex = vertex as usize * 3;
+				vec3(buffers.positions[vertex], buffers.positions[vertex + 1], buffers.positions[vertex + 2])
+			};
+			let rotation = options.rotation.transformation().unwrap();
+			for (quad, face) in buffers.faces.iter().enumerate() {
+				let normal = face.direction.get_normal().cast::<f64>().unwrap();
+				let expected = rotation.transform_vector(normal).cast::<f32>().unwrap();
+				for triangle in buffers.indices[quad * 6..quad * 6 + 6].chunks_exact(3) {
+					let [a, b, c] = [0, 1, 2].map(|corner| position(triangle[corner]));
+					let winding = (b - a).cross(c - a).normalize();
+					let dir = face.direction;
+					assert!((winding - expected).magnitude() < 1e-6, "{dir:?} at x {x} y {y} winds to {winding:?}");
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn unknown_rotation_keys_parse_and_are_kept() {
+		let json = br##"{"textures": {"all": "block/stone"}, "elements": [{"from": [0, 0, 0], "to": [16, 16, 16],
+			"rotation": {"angle": 22.5, "axis": "y", "origin": [8, 8, 8], "scale": [1, 2, 1], "offset": {"x": 0.5}},
+			"faces": {"up": {"texture": "#all"}}}]}"##;
+		let model = McModelJson::parse_json_model_slice(json).unwrap();
+		let rotation = model.elements[0].rotation.as_ref().unwrap();
+		assert_eq!(rotation.angle, 22.5);
+		assert!(matches!(rotation.axis, McModelRotationAxis::Y));
+		assert_eq!(rotation.extra.keys().collect::<Vec<_>>(), ["scale", "offset"]);
+		assert_eq!(rotation.extra["scale"], serde_json::json!([1, 2, 1]));
+
+		// Written back out they're still there for whatever understands them
+		let written = serde_json::to_value(&model).unwrap();
+		assert_eq!(written["elements"][0]["rotation"]["offset"], serde_json::json!({"x": 0.5}));
+		// And the rest of it builds as if they weren't there
+		model.to_cpu_mesh_from_source(&textures(&["block/stone"], 16)).unwrap();
+	}
+
+	#[test]
+	fn ambient_occlusion_off_survives_a_round_trip() {
+		let json = br#"{"parent": "block/cube_all", "ambientocclusion": false, "textures": {"all": "block/glass"},
+			"forge_marker": 1}"#;
+		let model = McModelJson::parse_json_model_slice(json).unwrap();
+		assert!(!model.ambient_occlusion());
+
+		let written = serde_json::to_vec(&model).unwrap();
+		let value: serde_json::Value = serde_json::from_slice(&written).unwrap();
+		assert_eq!(value["ambientocclusion"], serde_json::json!(false));
+		// Next to keys this crate doesn't know at all, which come back out too
+		assert_eq!(value["forge_marker"], serde_json::json!(1));
+		let reread = McModelJson::parse_json_model_slice(&written).unwrap();
+		assert_eq!(reread.ambient_occlusion, Some(false));
+		assert_eq!(serde_json::to_vec(&reread).unwrap(), written);
+
+		// Left out it stays left out rather than coming back as the default
+		let model = McModelJson::parse_json_model_slice(br#"{"parent": "block/cube_all"}"#).unwrap();
+		assert!(model.to_value().unwrap().get("ambientocclusion").is_none());
+	}
+
+	#[test]
+	fn flattening_stone_inlines_cube_all() {
+		const STONE: &[u8] = br#"{"parent": "block/cube_all", "textures": {"all": "block/stone"}}"#;
+		let mut loader = ModelLoader::new(templates::BuiltinTemplates);
+		let flattened = McModelJson::parse_json_model_slice(STONE).unwrap().flatten(&mut loader).unwrap();
+
+		assert_eq!(flattened.parent, None);
+		assert_eq!(flattened.elements.len(), 1);
+		assert_eq!(flattened.elements[0].faces.iter().count(), 6);
+		// `block/cube` maps each side to `#all` through `block/cube_all`, all of it down to the texture itself now
+		for tex_id in ["all", "particle", "down", "up", "north", "east", "south", "west"] {
+			assert_eq!(flattened.textures.get(tex_id).map(String::as_str), Some("block/stone"), "{tex_id}");
+		}
+
+		// Standing on its own it loads without any templates and builds the same as resolving stone does
+		let reread = McModelJson::parse_json_model_slice(&serde_json::to_vec(&flattened).unwrap()).unwrap();
+		assert_eq!(reread.resolved_textures().unwrap(), flattened.textures);
+		let textures = textures(&["block/stone"], 16);
+		let resolved = McModelJson::parse_json_model_slice(STONE).unwrap();
+		let resolved = resolved.resolve_parents(&templates::BuiltinTemplates).unwrap();
+		let options = MeshOptions::default();
+		assert_eq!(buffers(&reread, &textures, &options), buffers(&resolved, &textures, &options));
+	}
+
+	#[test]
+	fn from_normal_snaps_to_the_nearest_direction() {
+		for dir in DIRECTIONS {
+			assert_eq!(McModelDirection::from_normal(dir.get_normal()), Some(dir));
+			assert_eq!(McModelDirection::from_normal(dir.get_normal() * 0.3 + vec3(0.1, -0.1, 0.05)), Some(dir));
+		}
+		assert_eq!(McModelDirection::from_normal(vec3(0.0, 0.0, 0.0)), None);
+		assert_eq!(McModelDirection::from_normal(vec3(f32::NAN, 1.0, 0.0)), None);
+		assert_eq!(McModelDirection::from_normal(vec3(f32::INFINITY, 0.0, 0.0)), None);
+	}
+
+	#[test]
+	fn rotate_matches_the_rotation_matrices_in_all_24_orientations() {
+		use McModelRotationAxis::*;
+		let axis_vector = |axis: &McModelRotationAxis| match axis {
+			X => Vector3::unit_x(),
+			Y => Vector3::unit_y(),
+			Z => Vector3::unit_z(),
+		};
+		// Counter-clockwise looking back from the positive end is the right handed positive angle
+		let matrix = |axis: &McModelRotationAxis, turns: i32| {
+			Matrix3::from_axis_angle(axis_vector(axis), Deg(90.0 * turns as f32))
+		};
+
+		// Every orientation of a cube there is, found by turning further from those found so far until nothing new
+		// comes up, each as the turns it took and where it takes each direction
+		let mut orientations: Vec<(Matrix3<f32>, [McModelDirection; 6])> = vec![(Matrix3::identity(), DIRECTIONS)];
+		let mut next = 0;
+		while next < orientations.len() {
+			let (current, dirs) = orientations[next];
+			for axis in [X, Y, Z] {
+				for turns in [-1, 1, 2, 5] {
+					let rotated = matrix(&axis, turns) * current;
+					let dirs = dirs.map(|dir| dir.rotate(axis, turns));
+					// `rotate` has to agree with turning the normal itself
+					for (dir, rotated_dir) in DIRECTIONS.into_iter().zip(dirs) {
+						assert_eq!(McModelDirection::from_normal(rotated * dir.get_normal()), Some(rotated_dir));
+					}
+					if !orientations.iter().any(|(_, known)| *known == dirs) {
+						orientations.push((rotated, dirs));
+					}
+				}
+			}
+			next += 1;
+		}
+		assert_eq!(orientations.len(), 24);
+
+		for dir in DIRECTIONS {
+			for axis in [X, Y, Z] {
+				assert_eq!(dir.rotate(axis, 4), dir);
+				assert_eq!(dir.rotate(axis, 1).rotate(axis, -1), dir);
+			}
+		}
+		assert_eq!(McModelDirection::East.rotate(Y, 1), McModelDirection::North);
+	}
+
+	#[test]
+	fn blockstate_rotations_turn_every_face_where_rotate_says() {
+		let (model, textures) = (full_cube().build(), textures(&["block/stone"], 16));
+		let options = |x: i16, y: i16, uvlock: bool| MeshOptions {
+			rotation: McBlockstateRotation { x, y, uvlock },
+			uv_inset: Some(0.0),
+			..Default::default()
+		};
+		let plain = buffers(&model, &textures, &options(0, 0, false));
+		for x in [0, 90, 180, 270] {
+			for y in [0, 90, 180, 270] {
+				for uvlock in [false, true] {
+					let turned = buffers(&model, &textures, &options(x, y, uvlock));
+					for (quad, face) in turned.faces.iter().enumerate() {
+						// Blockstates turn clockwise, `x` first
+						let dir = face.direction.rotate(McModelRotationAxis::X, -x as i32 / 90);
+						let dir = dir.rotate(McModelRotationAxis::Y, -y as i32 / 90);
+						let corners = self::quad(&turned, quad);
+						let facing = |normal: [f32; 3]| McModelDirection::from_normal(normal.into());
+						assert!(corners.iter().all(|(_, _, normal)| facing(*normal) == Some(dir)));
+						// With uvlock the texture is where it was before, on whichever face ends up there
+						if uvlock {
+							let plain_corners = face_quad(&plain, dir);
+							for (pos, uv, _) in &corners {
+								let side = face.direction;
+								assert_eq!(uv_at(&plain_corners, *pos), *uv, "{side:?} at x {x} y {y} as {dir:?}");
+							}
+						}
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn blockstate_rotations_take_any_angle_the_json_has() {
+		let rotation = |x: i16, y: i16| McBlockstateRotation { x, y, uvlock: false }.transformation();
+		assert_eq!(rotation(-32760, 32760).unwrap(), Matrix4::identity());
+		assert_eq!(rotation(-270, 0).unwrap(), rotation(90, 0).unwrap());
+		assert_eq!(rotation(0, 450).unwrap(), rotation(0, 90).unwrap());
+		// Reported as the blockstate has them, these can't be negated without overflowing
+		assert!(matches!(rotation(i16::MIN, 0), Err(McModelError::UnsupportedBlockstateRotation(i16::MIN))));
+		assert!(matches!(rotation(0, i16::MIN), Err(McModelError::UnsupportedBlockstateRotation(i16::MIN))));
+		assert!(matches!(rotation(0, i16::MAX), Err(McModelError::UnsupportedBlockstateRotation(i16::MAX))));
+		assert!(matches!(rotation(45, 0), Err(McModelError::UnsupportedBlockstateRotation(45))));
+	}
+
+	#[test]
+	fn face_uvlock_keeps_textures_on_the_block_axes_under_element_rotation() {
+		let cube = |uvlock: bool| {
+			let mut model = top_and_north_cube();
+			model.elements[0].rotation = Some(McModelRotation::new(McModelRotationAxis::Y, 45.0, [8.0; 3]));
+			for (_, face) in model.elements[0].faces.iter_mut() {
+				face.uvlock = uvlock;
+			}
+			model
+		};
+		let textures = textures(&["block/stone"], 16);
+		let options = MeshOptions { uv_inset: Some(0.0), ..Default::default() };
+		let mut atlas = AtlasBuilder::new(&options.fallback).unwrap();
+		atlas.add_model_textures_from_source(&cube(false), &textures).unwrap();
+		let [x, y, ..] = atlas.mapping("block/stone").unwrap();
+		let unlocked = cube(false).to_mesh_buffers(&atlas, &options).unwrap();
+		let locked = cube(true).to_mesh_buffers(&atlas, &options).unwrap();
+		let unrotated = top_and_north_cube().to_mesh_buffers(&atlas, &options).unwrap();
+
+		// Without it the texture turns along with the top, each corner keeping the uv it had
+		let uvs = |buffers: &MeshBuffers, dir| face_quad(buffers, dir).iter().map(|c| c.1).collect::<Vec<_>>();
+		assert_eq!(uvs(&unlocked, McModelDirection::Up), uvs(&unrotated, McModelDirection::Up));
+
+		// With it `u` only goes along x and `v` only along z like on the unturned block, scaled down by the corners
+		// sticking out half a diagonal so they still land on the texture
+		let up = locked.faces.iter().position(|face| face.direction == McModelDirection::Up).unwrap();
+		let along = |start: u32, offset: f32| start as f32 + 8.0 + offset * std::f32::consts::FRAC_1_SQRT_2;
+		for corner in up * 4..up * 4 + 4 {
+			let (px, pz) = (locked.positions[corner * 3], locked.positions[corner * 3 + 2]);
+			let [u, v] = [locked.uvs[corner * 2], locked.uvs[corner * 2 + 1]].map(|c| c * INITIAL_ATLAS_SIZE as f32);
+			assert!((u - along(x, px - 8.0)).abs() < 1e-3, "u {u} at {px} {pz}");
+			assert!((v - along(y, pz - 8.0)).abs() < 1e-3, "v {v} at {px} {pz}");
+		}
+
+		// Faces the rotation doesn't spin in place have nothing to undo
+		assert_eq!(uvs(&locked, McModelDirection::North), uvs(&unlocked, McModelDirection::North));
+	}
+
+	#[test]
+	fn uvlock_takes_any_face_rotation_the_json_has() {
+		let textures = textures(&["block/stone"], 16);
+		let cube = |rotation: i16| {
+			let mut model = full_cube().build();
+			for (_, face) in model.elements[0].faces.iter_mut() {
+				face.rotation = rotation;
+			}
+			model
+		};
+		let locked = |rotation: i16| {
+			let mut atlas = AtlasBuilder::new(&FallbackTexture::default()).unwrap();
+			atlas.add_model_textures_from_source(&cube(rotation), &textures).unwrap();
+			cube(rotation).to_mesh_buffers(&atlas, &rotated(90, true))
+		};
+		// A whole number of turns away from 0 that takes it right to the edge of an `i16`, then past it with uvlock
+		assert_eq!(locked(-32760).unwrap(), locked(0).unwrap());
+		assert!(matches!(locked(i16::MIN), Err(McModelError::UnsupportedFaceRotation(_))));
+	}
+
+	#[test]
+	fn cullface_turns_with_the_blockstate_rotation() {
+		let mut model = McModelBuilder::new()
+			.texture("all", "block/stone")
+			.element([0.0; 3], [16.0; 3])
+			.face(McModelDirection::South, "#all", None)
+			.face(McModelDirection::North, "#all", None)
+			.build();
+		// Each culled by the neighbor on its own side, as full block sides are
+		for (dir, face) in model.elements[0].faces.iter_mut() {
+			face.cullface = Some(dir);
+		}
+		let textures = textures(&["block/stone"], 16);
+		let sides = |model: &McModelJson, y: i16, culled: &[McModelDirection]| {
+			let options = MeshOptions { culled: culled.to_vec(), ..rotated(y, false) };
+			buffers(model, &textures, &options).faces.iter().map(|face| face.direction).collect::<Vec<_>>()
+		};
+		use McModelDirection::*;
+		assert_eq!(sides(&model, 0, &[South]), [North]);
+		// Turned a quarter the south face looks west, so that's the neighbor hiding it and south hides nothing
+		assert_eq!(sides(&model, 90, &[West]), [North]);
+		assert_eq!(sides(&model, 90, &[South]), [North, South]);
+		assert_eq!(sides(&model, 90, &[East]), [South]);
+		assert_eq!(sides(&model, 180, &[North]), [North]);
+
+		// The element's own rotation only moves the face around inside the block, it still goes by south
+		let mut tilted = model.clone();
+		tilted.elements[0].rotation = Some(McModelRotation::new(McModelRotationAxis::Y, 22.5, [8.0; 3]));
+		assert_eq!(sides(&tilted, 0, &[South]), [North]);
+		assert_eq!(sides(&tilted, 90, &[West]), [North]);
+	}
+
+	#[test]
+	fn face_rotations_wrap_around() {
+		let textures = textures(&["block/stone"], 16);
+		let model = |rotation: i16| {
+			let json = format!(
+				r##"{{"textures": {{"all": "block/stone"}}, "elements": [{{"from": [0, 0, 0], "to": [16, 16, 16],
+					"faces": {{"up": {{"texture": "#all", "uv": [0, 0, 8, 16], "rotation": {rotation}}}}}}}]}}"##
+			);
+			McModelJson::parse_json_model_slice(json.as_bytes()).unwrap()
+		};
+		let up = |rotation: i16| buffers(&model(rotation), &textures, &MeshOptions::default());
+		assert_eq!(up(-90), up(270));
+		assert_eq!(up(360), up(0));
+		assert_eq!(up(-270), up(90));
+		// They are turned still, not just read as no rotation
+		assert_ne!(up(-90), up(0));
+
+		let mut atlas = AtlasBuilder::new(&FallbackTexture::default()).unwrap();
+		atlas.add_model_textures_from_source(&model(45), &textures).unwrap();
+		let error = model(45).to_mesh_buffers(&atlas, &MeshOptions::default()).unwrap_err();
+		assert!(matches!(error, McModelError::UnsupportedFaceRotation(45)));
+	}
+
+	#[test]
+	fn diamond_sword_is_held_the_way_vanilla_places_it() {
+		// The vanilla models on the way, diamond_sword sets no display of its own
+		struct Items;
+		impl ModelSource for Items {
+			fn load_model(&self, location: &str) -> anyhow::Result<McModelJson> {
+				let json: &[u8] = match McResourceLocation::parse(location).path.as_str() {
+					"item/handheld" => br#"{"parent": "item/generated", "display": {
+						"thirdperson_righthand": {"rotation": [0, -90, 55], "translation": [0, 4.0, 0.5],
+							"scale": [0.85, 0.85, 0.85]},
+						"thirdperson_lefthand": {"rotation": [0, 90, -55], "translation": [0, 4.0, 0.5],
+							"scale": [0.85, 0.85, 0.85]},
+						"firstperson_righthand": {"rotation": [0, -90, 25], "translation": [1.13, 3.2, 1.13],
+							"scale": [0.68, 0.68, 0.68]},
+						"firstperson_lefthand": {"rotation": [0, 90, -25], "translation": [1.13, 3.2, 1.13],
+							"scale": [0.68, 0.68, 0.68]}}}"#,
+					"item/generated" => br#"{"parent": "builtin/generated", "gui_light": "front", "display": {
+						"ground": {"rotation": [0, 0, 0], "translation": [0, 2, 0], "scale": [0.5, 0.5, 0.5]},
+						"head": {"rotation": [0, 180, 0], "translation": [0, 13, 7], "scale": [1, 1, 1]},
+						"thirdperson_righthand": {"rotation": [0, 0, 0], "translation": [0, 3, 1],
+							"scale": [0.55, 0.55, 0.55]},
+						"firstperson_righthand": {"rotation": [0, -90, 25], "translation": [1.13, 3.2, 1.13],
+							"scale": [0.68, 0.68, 0.68]},
+						"fixed": {"rotation": [0, 180, 0], "scale": [1, 1, 1]}}}"#,
+					_ => anyhow::bail!("no model {location}"),
+				};
+				Ok(McModelJson::parse_json_model_slice(json)?)
+			}
+		}
+		let sword = br#"{"parent": "minecraft:item/handheld", "textures": {"layer0": "minecraft:item/diamond_sword"}}"#;
+		let sword = McModelJson::parse_json_model_slice(sword).unwrap().resolve_parents(&Items).unwrap();
+		let display = sword.display.unwrap();
+
+		// Where points in model space end up, worked out from the game's own steps: translate by translation / 16,
+		// rotate by the quaternion of `rotationXYZ` on the json's angles, scale, all about the block center
+		let place = |transform: &McModelTransform, [x, y, z]: [f32; 3]| {
+			let placed = transform.to_matrix() * vec4(x, y, z, 1.0);
+			[placed.x, placed.y, placed.z]
+		};
+		let assert_places = |transform: &McModelTransform, point: [f32; 3], expected: [f32; 3]| {
+			let placed = place(transform, point);
+			assert!(placed.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-4), "{point:?} went to {placed:?}");
+		};
+		// item/handheld's own third person slot wins over item/generated's
+		let third = display.thirdperson_righthand.as_ref().unwrap();
+		assert_eq!(third.rotation, [0.0, -90.0, 55.0]);
+		assert_places(third, [8.0, 8.0, 8.0], [8.0, 12.0, 8.5]);
+		assert_places(third, [9.0, 8.0, 8.0], [8.0, 12.696_28, 8.987_54]);
+		assert_places(third, [8.0, 9.0, 8.0], [8.0, 12.487_54, 7.803_72]);
+		let first = display.firstperson_righthand.as_ref().unwrap();
+		assert_places(first, [9.0, 8.0, 8.0], [9.13, 11.487_38, 9.746_29]);
+		// And the slots only item/generated sets come through from it
+		assert_places(display.ground.as_ref().unwrap(), [9.0, 8.0, 8.0], [8.5, 10.0, 8.0]);
+		assert_places(display.head.as_ref().unwrap(), [9.0, 8.0, 8.0], [7.0, 21.0, 15.0]);
+		assert!(display.gui.is_none());
+
+		// X is the outermost rotation, applied last, so a quarter turn on X and Y together sends +X up
+		let turned = McModelTransform { rotation: [90.0, 90.0, 0.0], ..McModelTransform::default() };
+		assert_places(&turned, [9.0, 8.0, 8.0], [8.0, 9.0, 8.0]);
+		// Out of range translations and scales get clamped, negative scales still mirror
+		let extreme = McModelTransform {
+			rotation: [0.0; 3],
+			translation: [0.0, 100.0, -100.0],
+			scale: [5.0, -5.0, 1.0],
+		};
+		assert_places(&extreme, [8.0, 8.0, 8.0], [8.0, 88.0, -72.0]);
+		assert_places(&extreme, [9.0, 9.0, 9.0], [12.0, 84.0, -71.0]);
+		// Right at the ends of both ranges nothing gets clamped, and scales below -4 only go down to it
+		let edge = McModelTransform { rotation: [0.0; 3], translation: [80.0, -80.0, 0.0], scale: [-4.0, 4.0, -3.5] };
+		assert_places(&edge, [9.0, 9.0, 9.0], [84.0, -68.0, 4.5]);
+		let below = McModelTransform { scale: [-4.5, 0.0, -0.25], ..edge.clone() };
+		assert_places(&below, [9.0, 9.0, 9.0], [84.0, -72.0, 7.75]);
+	}
+
+	#[test]
+	fn unshaded_elements_keep_white_vertex_colors() {
+		let model = DIRECTIONS
+			.into_iter()
+			.fold(full_cube().shade(false).element([4.0; 3], [12.0; 3]), |builder, dir| builder.face(dir, "#all", None))
+			.build();
+		assert!(!model.elements[0].shade);
+		assert!(model.elements[1].shade);
+		let textures = textures(&["block/stone"], 16);
+		let buffers = buffers(&model, &textures, &MeshOptions::default());
+
+		assert_eq!(buffers.faces.len(), 12);
+		for (index, face) in buffers.faces.iter().enumerate() {
+			let mult = if face.element_idx == 0 { 1.0 } else { face.direction.get_shading_mult() };
+			for vertex in index * 4..index * 4 + 4 {
+				let color = &buffers.colors[vertex * 4..vertex * 4 + 4];
+				let expected = [mult, mult, mult, 1.0];
+				assert!(color.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1.0 / 255.0), "{face:?}: {color:?}");
+			}
+		}
+
+		// Sampling the faces leaves the unshaded one alone the same way
+		let options = SampleOptions { shading: true, ..Default::default() };
+		let sample = |element_idx| {
+			model.sample_face_color_from_source(&textures, element_idx, McModelDirection::North, 8.0, 8.0, &options)
+		};
+		assert_eq!(sample(0), Some(Srgba::WHITE));
+		assert_eq!(sample(1), Some(Srgba::new(204, 204, 204, 255)));
+
+		// Only the unshaded one says so when written out
+		let value = model.to_value().unwrap();
+		assert_eq!(value["elements"][0]["shade"], serde_json::json!(false));
+		assert!(value["elements"][1].get("shade").is_none());
+	}
+
+	#[test]
+	fn only_textures_the_faces_use_get_packed() {
+		let model = McModelBuilder::new()
+			.texture("side", "block/stone")
+			.texture("top", "#lid")
+			.texture("lid", "block/oak_planks")
+			.texture("unused", "block/dirt")
+			.texture("particle", "#side")
+			.element([0.0; 3], [16.0; 3])
+			.face(McModelDirection::North, "#side", None)
+			.face(McModelDirection::Up, "#top", None)
+			.face(McModelDirection::South, "#side", None)
+			.face(McModelDirection::Down, "#missing", None)
+			.build();
+		assert_eq!(model.used_textures().into_iter().collect::<Vec<_>>(), ["side", "top", "missing"]);
+
+		let mut atlas = AtlasBuilder::new(&FallbackTexture::default()).unwrap();
+		let fallback_space = atlas.allocator.allocated_space();
+		let textures = textures(&["block/stone", "block/oak_planks", "block/dirt"], 16);
+		atlas.add_model_textures_from_source(&model, &textures).unwrap();
+		// `top` gets to its texture through `lid`, `unused` is declared but only takes up space if something shows it
+		assert!(atlas.mapping("block/stone").is_some());
+		assert!(atlas.mapping("block/oak_planks").is_some());
+		assert_eq!(atlas.mapping("block/dirt"), None);
+		assert_eq!(atlas.allocator.allocated_space() - fallback_space, 2 * 16 * 16);
+		let mappings = atlas.model_mappings(&model).unwrap();
+		assert!(mappings.contains_key("top"));
+		assert!(!mappings.contains_key("unused"));
+
+		// The reference to a variable nobody defines is reported instead of quietly drawn with the fallback
+		assert_eq!(atlas.errors().len(), 1);
+		assert_eq!(atlas.errors()[0].id, "missing");
+		let error = atlas.errors()[0].source.downcast_ref::<McModelError>();
+		assert!(matches!(error, Some(McModelError::UndefinedTextureVariable(var)) if var == "missing"));
+	}
+
+	#[test]
+	fn sampling_an_empty_texture_finds_nothing() {
+		let model = full_cube().build();
+		let options = SampleOptions::default();
+		let sample = |textures: &HashMap<String, RgbaImage>| {
+			model.sample_face_color_from_source(textures, 0, McModelDirection::North, 8.0, 8.0, &options)
+		};
+		assert_eq!(sample(&textures(&["block/stone"], 16)), Some(Srgba::WHITE));
+		for (width, height) in [(0, 0), (0, 16), (16, 0)] {
+			let empty = HashMap::from([("block/stone".to_string(), RgbaImage::new(width, height))]);
+			assert_eq!(sample(&empty), None, "{width}x{height}");
+		}
+	}
+
+	#[test]
+	fn empty_textures_are_never_packed() {
+		let model = full_cube().build();
+		let textures = HashMap::from([("block/stone".to_string(), RgbaImage::new(0, 16))]);
+		let mut atlas = AtlasBuilder::new(&FallbackTexture::default()).unwrap();
+		let fallback_space = atlas.allocator.allocated_space();
+		atlas.add_model_textures_from_source(&model, &textures).unwrap();
+		// Left to the fallback instead of growing the atlas looking for room
+		assert_eq!(atlas.image.width(), INITIAL_ATLAS_SIZE);
+		assert_eq!(atlas.allocator.allocated_space(), fallback_space);
+		assert_eq!(atlas.mapping("block/stone"), None);
+		assert_eq!(atlas.errors()[0].source.to_string(), "texture minecraft:block/stone is empty at 0x16");
+
+		// An empty fallback has nothing to fall back on in turn
+		for fallback in [FallbackTexture::Checkerboard { size: 0 }, FallbackTexture::Image(RgbaImage::new(0, 0))] {
+			let error = AtlasBuilder::new(&fallback).err().unwrap();
+			assert!(matches!(error, McModelError::EmptyImage { ref id, .. } if id == "not-found"), "{error}");
+		}
+	}
+
+	#[test]
+	fn models_past_the_limits_load_no_textures() {
+		use std::sync::atomic::{AtomicUsize, Ordering};
+		struct Counting(AtomicUsize);
+		impl TextureSource for Counting {
+			fn load(&self, _id: &str) -> Option<RgbaImage> {
+				self.0.fetch_add(1, Ordering::Relaxed);
+				Some(RgbaImage::new(16, 16))
+			}
+		}
+		let model = DIRECTIONS
+			.into_iter()
+			.fold(full_cube().element([4.0; 3], [12.0; 3]), |builder, dir| builder.face(dir, "#all", None))
+			.build();
+		let textures = Counting(Default::default());
+		let mut atlas = AtlasBuilder::new(&FallbackTexture::default()).unwrap();
+		let elements = MeshLimits { max_elements: 1, ..Default::default() };
+		let faces = MeshLimits { max_faces: 11, ..Default::default() };
+		for limits in [elements, faces] {
+			let options = MeshOptions { limits, ..Default::default() };
+			let past = |error: McModelError| {
+				use McModelError::{TooManyElements, TooManyFaces};
+				matches!(error, TooManyElements { count: 2, max: 1 } | TooManyFaces { count: 12, max: 11 })
+			};
+			assert!(past(model.to_cpu_mesh_with_options(&textures, &options).err().unwrap()));
+			assert!(past(model.to_cpu_mesh_reusing(&textures, &options, &mut atlas).err().unwrap()));
+		}
+		assert_eq!(textures.0.load(Ordering::Relaxed), 0);
+
+		// Right at them it's built as usual
+		let options = MeshOptions { limits: MeshLimits { max_elements: 2, max_faces: 12 }, ..Default::default() };
+		model.to_cpu_mesh_with_options(&textures, &options).unwrap();
+		assert_eq!(textures.0.load(Ordering::Relaxed), 1);
+	}
+
+	#[test]
+	fn strict_parsing_only_knows_vanilla_and_this_crates_keys() {
+		let json = br##"{"parent": "block/block", "credit": "Made with Blockbench", "textures": {"all": "block/stone"},
+			"elements": [{"from": [0, 0, 0], "to": [16, 16, 16], "light_emission": 15, "shade": false,
+				"faces": {"up": {"texture": "#all", "tintindex": 0, "emissive": true, "uvlock": true}}}]}"##;
+		McModelJson::parse_json_model_strict(json).unwrap();
+
+		let json = br##"{"forge_data": {}, "elements": [{"from": [0, 0, 0], "to": [16, 16, 16],
+			"faces": {"up": {"texture": "#all", "textrue": "#all"}}}]}"##;
+		let Err(McModelError::UnknownKeys(mut keys)) = McModelJson::parse_json_model_strict(json) else {
+			panic!("forge_data and the typo weren't both caught");
+		};
+		keys.sort();
+		assert_eq!(keys, ["elements[0].faces.up.textrue", "forge_data"]);
+		// Which the lenient parse keeps around
+		assert!(McModelJson::parse_json_model_slice(json).unwrap().extra.contains_key("forge_data"));
+	}
+
+	#[test]
+	fn rescaling_stays_finite_past_vanilla_angles() {
+		let textures = textures(&["block/stone"], 16);
+		let extent = |angle: f64| {
+			let mut model = full_cube().build();
+			let mut rotation = McModelRotation::new(McModelRotationAxis::Y, angle, [8.0; 3]);
+			rotation.rescale = true;
+			model.elements[0].rotation = Some(rotation);
+			let buffers = buffers(&model, &textures, &MeshOptions::default());
+			buffers.positions.chunks(3).map(|pos| pos[0]).fold(f32::MIN, f32::max)
+		};
+		// The vanilla angles stretch a corner of the turned square out to `8 * (1 + tan(angle))` from the center
+		let stretched = |angle: f64| 8.0 + 8.0 * (1.0 + angle.to_radians().tan()) as f32;
+		assert!((extent(22.5) - stretched(22.5)).abs() < 1e-3);
+		assert!((extent(45.0) - 24.0).abs() < 1e-3);
+		// Beyond them it goes by the nearest quarter turn, so a quarter turn lines right back up with the block
+		assert!((extent(67.5) - stretched(22.5)).abs() < 1e-3);
+		assert!((extent(90.0) - 16.0).abs() < 1e-3);
+		assert!((extent(-90.0) - 16.0).abs() < 1e-3);
+	}
+}
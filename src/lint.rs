@@ -0,0 +1,255 @@
+//! Machine readable diagnostics about a model, for editor integrations and pack checking tools to show without
+//! anything failing to load over them.
+
+use three_d::*;
+
+use crate::{
+	minmax, ElementTransform, McModelDisplay, McModelJson, DISPLAY_SCALE_RANGE, DISPLAY_TRANSLATION_RANGE,
+	VANILLA_COORDINATE_RANGE,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+	/// Worth knowing but often on purpose
+	Info,
+	/// Loads fine but probably isn't what was meant
+	Warning,
+	/// Something that will show up wrong, like a missing texture
+	Error,
+}
+
+impl std::fmt::Display for Severity {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			Severity::Info => "info",
+			Severity::Warning => "warning",
+			Severity::Error => "error",
+		})
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+	pub severity: Severity,
+	/// Where in the model json it is, like `elements[0].faces.north.uv`, empty for the model as a whole
+	pub path: String,
+	pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if self.path.is_empty() {
+			write!(f, "{}: {}", self.severity, self.message)
+		} else {
+			write!(f, "{}: {}: {}", self.severity, self.path, self.message)
+		}
+	}
+}
+
+impl McModelJson {
+	/// Everything questionable about the model that it still loads with: texture variables nothing uses, faces using
+	/// variables that aren't defined, elements without any volume or duplicating another, faces of different elements
+	/// on top of each other that z-fight, uvs reaching outside of 0-16, coordinates outside of what vanilla accepts and
+	/// `display` values the game would clamp.  The texture checks only make sense on a model with its parents
+	/// resolved, a template like `block/cube` leaves its variables for the children to define.
+	pub fn lint(&self) -> Vec<Diagnostic> {
+		let mut diagnostics = Vec::new();
+		let mut report = |severity: Severity, path: String, message: String| {
+			diagnostics.push(Diagnostic {
+				severity,
+				path,
+				message,
+			})
+		};
+
+		if let Some(loader) = self.custom_loader() {
+			report(
+				Severity::Error,
+				String::new(),
+				format!("needs the custom model loader `{loader}`, there's no vanilla geometry to check"),
+			);
+		}
+
+		let mut referenced = Vec::new();
+		for (element_idx, element) in self.elements.iter().enumerate() {
+			let path = format!("elements[{element_idx}]");
+			let axes = ["x", "y", "z"];
+			let flat = (0..3)
+				.filter(|&axis| element.from[axis] == element.to[axis])
+				.map(|axis| axes[axis])
+				.collect::<Vec<_>>();
+			match flat.len() {
+				0 => {}
+				1 => report(
+					Severity::Info,
+					path.clone(),
+					format!("element is flat along {}", flat[0]),
+				),
+				_ => report(
+					Severity::Warning,
+					path.clone(),
+					format!(
+						"element has no extent along {}, so nothing of it can show",
+						flat.join(" and ")
+					),
+				),
+			}
+			for axis in (0..3).filter(|&axis| element.from[axis] > element.to[axis]) {
+				report(Severity::Warning, path.clone(), format!("`from` is past `to` along {}", axes[axis]));
+			}
+			for (key, coords) in [("from", element.from), ("to", element.to)] {
+				if coords.iter().any(|c| !VANILLA_COORDINATE_RANGE.contains(c)) {
+					report(
+						Severity::Warning,
+						format!("{path}.{key}"),
+						format!("{coords:?} is outside of the {:?} vanilla accepts", VANILLA_COORDINATE_RANGE),
+					);
+				}
+			}
+			// Elements have no equality of their own, but serializing them compares every field there is
+			let value = serde_json::to_value(element).ok();
+			if let Some(duplicate) = self.elements[..element_idx]
+				.iter()
+				.position(|other| serde_json::to_value(other).ok() == value)
+			{
+				report(
+					Severity::Warning,
+					path.clone(),
+					format!("element is identical to elements[{duplicate}]"),
+				);
+			}
+
+			for (dir, face) in element.faces.iter() {
+				let path = format!("{path}.faces.{}", dir.name());
+				if let Some(uv) = face.uv {
+					if uv.iter().any(|c| !(0.0..=16.0).contains(c)) {
+						report(Severity::Warning, format!("{path}.uv"), format!("uv {uv:?} reaches outside of 0-16"));
+					}
+				}
+				let Some(var) = face.texture.strip_prefix('#') else {
+					report(
+						Severity::Error,
+						format!("{path}.texture"),
+						format!("`{}` should be a `#variable` reference", face.texture),
+					);
+					continue;
+				};
+				match self.resolve_texture(var) {
+					Ok(Some(_)) => {}
+					Ok(None) => report(
+						Severity::Error,
+						format!("{path}.texture"),
+						format!("texture variable `#{var}` isn't defined"),
+					),
+					Err(error) => report(Severity::Error, format!("{path}.texture"), error.to_string()),
+				}
+				referenced.push(var);
+			}
+		}
+
+		// Faces of different elements right on top of each other facing the same way, which the depth test can't tell
+		// apart so they flicker between each other
+		let quads = self
+			.elements
+			.iter()
+			.enumerate()
+			.flat_map(|(element_idx, element)| {
+				let transform = ElementTransform::new(element.rotation.as_ref());
+				let [[x0, x1], [y0, y1], [z0, z1]] = [0, 1, 2].map(|axis| minmax(element.from[axis], element.to[axis]));
+				let (p0, p1) = (vec3(x0, y0, z0), vec3(x1, y1, z1));
+				element.faces.iter().map(move |(dir, _)| {
+					(
+						element_idx,
+						dir,
+						dir.quad_corners(p0, p1).map(|corner| transform.pos(corner)),
+					)
+				})
+			})
+			.collect::<Vec<_>>();
+		for (idx, (element_a, dir_a, quad_a)) in quads.iter().enumerate() {
+			for (element_b, dir_b, quad_b) in &quads[idx + 1..] {
+				if element_a != element_b && z_fights(quad_a, quad_b) {
+					let other = format!("elements[{element_a}].faces.{}", dir_a.name());
+					report(
+						Severity::Warning,
+						format!("elements[{element_b}].faces.{}", dir_b.name()),
+						format!("face lies on top of {other} facing the same way, the two will z-fight"),
+					);
+				}
+			}
+		}
+
+		// Variables only used through another variable's `#` reference still count as used
+		let mut idx = 0;
+		while let Some(&var) = referenced.get(idx) {
+			if let Some(next) = self.textures.get(var).and_then(|value| value.strip_prefix('#')) {
+				if !referenced.contains(&next) {
+					referenced.push(next);
+				}
+			}
+			idx += 1;
+		}
+		for slot in McModelDisplay::SLOT_NAMES {
+			let Some(transform) = self.display.as_ref().and_then(|display| display.slot(slot)) else {
+				continue;
+			};
+			for (key, values, range) in [
+				("translation", transform.translation, DISPLAY_TRANSLATION_RANGE),
+				("scale", transform.scale, DISPLAY_SCALE_RANGE),
+			] {
+				if values.iter().any(|c| !range.contains(c)) {
+					report(
+						Severity::Warning,
+						format!("display.{slot}.{key}"),
+						format!("{values:?} is outside of the {range:?} the game clamps it to"),
+					);
+				}
+			}
+		}
+
+		// Without elements the variables are for something else to use, like the parent's elements or `layer0` and on
+		// for generated item models
+		if !self.elements.is_empty() {
+			for tex_id in self.textures.keys() {
+				if !referenced.contains(&tex_id.as_str()) && !McModelJson::is_non_face_texture(tex_id) {
+					report(
+						Severity::Warning,
+						format!("textures.{tex_id}"),
+						format!("texture variable `{tex_id}` isn't used by any face"),
+					);
+				}
+			}
+		}
+		diagnostics
+	}
+}
+
+/// Whether two face quads are in the same plane facing the same way and cover some of the same area of it
+fn z_fights(a: &[Vector3<f64>; 4], b: &[Vector3<f64>; 4]) -> bool {
+	const EPSILON: f64 = 1e-4;
+	let normal = |quad: &[Vector3<f64>; 4]| (quad[1] - quad[0]).cross(quad[2] - quad[1]);
+	let (normal_a, normal_b) = (normal(a), normal(b));
+	// Faces without any area don't draw anything to fight over
+	if normal_a.magnitude2() < EPSILON || normal_b.magnitude2() < EPSILON {
+		return false;
+	}
+	let (normal_a, normal_b) = (normal_a.normalize(), normal_b.normalize());
+	if normal_a.dot(normal_b) < 1.0 - EPSILON || normal_a.dot(b[0] - a[0]).abs() > EPSILON {
+		return false;
+	}
+	// Two convex quads in a plane overlap unless a line along one of their edges separates them, and only touching
+	// along an edge doesn't count
+	let edges = [a[1] - a[0], a[2] - a[1], b[1] - b[0], b[2] - b[1]];
+	edges.iter().all(|edge| {
+		let axis = normal_a.cross(*edge).normalize();
+		let extent = |quad: &[Vector3<f64>; 4]| {
+			quad.iter()
+				.map(|corner| corner.dot(axis))
+				.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), at| {
+					(min.min(at), max.max(at))
+				})
+		};
+		let ((min_a, max_a), (min_b, max_b)) = (extent(a), extent(b));
+		max_a.min(max_b) - min_a.max(min_b) > EPSILON
+	})
+}
@@ -0,0 +1,122 @@
+//! Outlines of the covered region of an alpha mask, like the silhouette
+//! [`render::silhouette_mask`](crate::render) renders, as polygons to draw hover and selection highlights with.
+//!
+//! The mask gets traced with marching squares over the pixel centers, so edges run half way between covered and
+//! uncovered pixels, and the staircase that leaves along slanted edges is simplified away afterwards.
+
+use std::collections::{HashMap, HashSet};
+
+use image::RgbaImage;
+use three_d::*;
+
+/// The boundaries of where `mask` has an alpha above `alpha_threshold`, each a closed polygon in pixel coordinates
+/// (y down, `(0, 0)` the top left corner of the image) without its first point repeated at the end.  Separate
+/// regions and holes in them each get a polygon of their own, and pixels only touching at a corner count as separate.
+///
+/// Points closer than `tolerance` pixels to the line the simplified polygon takes instead are dropped, 0 only drops
+/// the ones in the middle of straight runs, around 1 smooths out the steps of slanted edges.
+pub fn trace_outline(mask: &RgbaImage, alpha_threshold: u8, tolerance: f32) -> Vec<Vec<Vec2>> {
+	let (width, height) = (mask.width() as i64, mask.height() as i64);
+	let inside = |x: i64, y: i64| {
+		x >= 0 && y >= 0 && x < width && y < height && mask.get_pixel(x as u32, y as u32).0[3] > alpha_threshold
+	};
+
+	// Points are kept at twice their coordinate so the edge midpoints they sit on are whole numbers, each one ends
+	// up joined to exactly two others as every cell edge is shared by two cells and crossed at most once in each
+	let mut neighbors: HashMap<(i64, i64), Vec<(i64, i64)>> = HashMap::new();
+	let mut found = Vec::new();
+	for cy in -1..height {
+		for cx in -1..width {
+			let case = (inside(cx, cy) as u8) << 3
+				| (inside(cx + 1, cy) as u8) << 2
+				| (inside(cx + 1, cy + 1) as u8) << 1
+				| inside(cx, cy + 1) as u8;
+			let top = (2 * cx + 2, 2 * cy + 1);
+			let bottom = (2 * cx + 2, 2 * cy + 3);
+			let left = (2 * cx + 1, 2 * cy + 2);
+			let right = (2 * cx + 3, 2 * cy + 2);
+			// Corners are top left, top right, bottom right and bottom left from the highest bit down, and the two
+			// saddles cut their covered corners off from each other
+			let segments: &[_] = match case {
+				1 | 14 => &[(left, bottom)],
+				2 | 13 => &[(bottom, right)],
+				3 | 12 => &[(left, right)],
+				4 | 11 => &[(top, right)],
+				5 => &[(top, right), (left, bottom)],
+				6 | 9 => &[(top, bottom)],
+				7 | 8 => &[(top, left)],
+				10 => &[(top, left), (bottom, right)],
+				_ => &[],
+			};
+			for &(a, b) in segments {
+				if !neighbors.contains_key(&a) {
+					found.push(a);
+				}
+				neighbors.entry(a).or_default().push(b);
+				neighbors.entry(b).or_default().push(a);
+			}
+		}
+	}
+
+	// Starting each one where the scan first came across it keeps the output the same from run to run
+	let mut outlines = Vec::new();
+	let mut traced = HashSet::new();
+	for start in found {
+		if traced.contains(&start) {
+			continue;
+		}
+		let mut outline = vec![start];
+		let mut previous = start;
+		let mut current = neighbors[&start][0];
+		while current != start {
+			outline.push(current);
+			let next = neighbors[&current].iter().copied().find(|&point| point != previous).unwrap_or(start);
+			(previous, current) = (current, next);
+		}
+		traced.extend(outline.iter().copied());
+		let outline = outline.into_iter().map(|(x, y)| vec2(x as f32 / 2.0, y as f32 / 2.0)).collect::<Vec<_>>();
+		outlines.push(simplify_closed(&outline, tolerance));
+	}
+	outlines
+}
+
+/// Ramer-Douglas-Peucker over a closed polygon, split in two at the point furthest from its first
+fn simplify_closed(polygon: &[Vec2], tolerance: f32) -> Vec<Vec2> {
+	let Some(far) = (1..polygon.len()).max_by(|&a, &b| {
+		(polygon[a] - polygon[0])
+			.magnitude2()
+			.total_cmp(&(polygon[b] - polygon[0]).magnitude2())
+	}) else {
+		return polygon.to_vec();
+	};
+	let mut closed = polygon.to_vec();
+	closed.push(polygon[0]);
+	let mut simplified = simplify(&closed[..=far], tolerance);
+	simplified.pop();
+	simplified.extend(simplify(&closed[far..], tolerance));
+	simplified.pop();
+	simplified
+}
+
+/// Ramer-Douglas-Peucker over an open line, keeping both of its ends
+fn simplify(line: &[Vec2], tolerance: f32) -> Vec<Vec2> {
+	let (first, last) = (line[0], line[line.len() - 1]);
+	let direction = last - first;
+	let distance = |point: Vec2| {
+		if direction.magnitude2() == 0.0 {
+			(point - first).magnitude()
+		} else {
+			(direction.x * (point.y - first.y) - direction.y * (point.x - first.x)).abs() / direction.magnitude()
+		}
+	};
+	let furthest = (1..line.len() - 1).map(|idx| (idx, distance(line[idx]))).max_by(|(_, a), (_, b)| a.total_cmp(b));
+	match furthest {
+		Some((idx, distance)) if distance > tolerance => {
+			let mut simplified = simplify(&line[..=idx], tolerance);
+			simplified.pop();
+			simplified.extend(simplify(&line[idx..], tolerance));
+			simplified
+		}
+		_ => vec![first, last],
+	}
+}
@@ -0,0 +1,541 @@
+//! Resource packs as the game reads them, either as a directory or straight out of a zip (or a game jar, which is
+//! just a zip with a different extension) without extracting it first.
+//!
+//! Only the subset of zip that packs and jars actually use is read: stored and deflated entries in archives without
+//! zip64 extensions or encryption.  Every size and offset in the archive is checked against the archive itself before
+//! anything is read or allocated by it, so a broken or malicious pack fails to load rather than taking all the memory.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use image::RgbaImage;
+
+use crate::animation::McTextureAnimation;
+use crate::{
+	McAssetsRoot, McModelError, McModelJson, McResourceLocation, ModelSource, TextureSource, TEXTURE_EXTENSIONS,
+};
+
+/// Where a pack's `assets/<namespace>/{models,textures}/<path>` files come from
+#[derive(Clone, Debug)]
+pub enum ResourcePack {
+	Directory(McAssetsRoot),
+	Zip(ZipArchive),
+}
+
+impl ResourcePack {
+	/// Opens a directory as is, anything else is read as a zip archive
+	pub fn open(path: impl AsRef<Path>) -> anyhow::Result<ResourcePack> {
+		let path = path.as_ref();
+		if path.is_dir() {
+			Ok(ResourcePack::Directory(McAssetsRoot::new(path)))
+		} else {
+			Ok(ResourcePack::Zip(ZipArchive::open(path)?))
+		}
+	}
+
+	/// Reads a file by its path inside the pack, such as `assets/minecraft/models/block/stone.json`, `None` if the
+	/// pack doesn't have it
+	pub fn read(&self, path: &str) -> anyhow::Result<Option<Vec<u8>>> {
+		match self {
+			ResourcePack::Directory(assets) => {
+				let path = assets.root.join(path);
+				match std::fs::read(&path) {
+					Ok(data) => Ok(Some(data)),
+					Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+					Err(error) => Err(error).with_context(|| format!("unable to read {path:?}")),
+				}
+			}
+			ResourcePack::Zip(archive) => archive.read(path),
+		}
+	}
+
+	/// The in-pack path of a model's json
+	pub fn model_path(location: &str) -> String {
+		let location = McResourceLocation::parse(location);
+		format!("assets/{}/models/{}.json", location.namespace, location.path)
+	}
+
+	/// The in-pack path of a texture without its extension
+	pub fn texture_path_stem(location: &str) -> String {
+		let location = McResourceLocation::parse(location);
+		format!("assets/{}/textures/{}", location.namespace, location.path)
+	}
+
+	/// The raw bytes of a texture file and the extension it was found with, trying [`TEXTURE_EXTENSIONS`] in order
+	pub fn texture_bytes(&self, id: &str) -> anyhow::Result<Option<(Vec<u8>, &'static str)>> {
+		let stem = ResourcePack::texture_path_stem(id);
+		for extension in TEXTURE_EXTENSIONS {
+			if let Some(data) = self.read(&format!("{stem}.{extension}"))? {
+				return Ok(Some((data, extension)));
+			}
+		}
+		Ok(None)
+	}
+
+	/// Whether the pack has a file by its path inside the pack, without reading it
+	pub fn contains(&self, path: &str) -> bool {
+		match self {
+			ResourcePack::Directory(assets) => assets.root.join(path).is_file(),
+			ResourcePack::Zip(archive) => archive.entries.contains_key(path),
+		}
+	}
+
+	/// The in-pack path of the texture with the first of [`TEXTURE_EXTENSIONS`] the pack has it with
+	fn find_texture_path(&self, id: &str) -> Option<String> {
+		let stem = ResourcePack::texture_path_stem(id);
+		TEXTURE_EXTENSIONS.iter().map(|extension| format!("{stem}.{extension}")).find(|path| self.contains(path))
+	}
+
+	/// A file inside the pack for messages, zip entries written like jar urls as `pack.zip!/assets/...`
+	fn describe_path(&self, path: &str) -> String {
+		match self {
+			ResourcePack::Directory(assets) => crate::describe_path(&assets.root.join(path)),
+			ResourcePack::Zip(archive) => format!("{}!/{path}", crate::describe_path(&archive.path)),
+		}
+	}
+
+	/// Where the pack itself is, for messages
+	pub fn path(&self) -> &Path {
+		match self {
+			ResourcePack::Directory(assets) => &assets.root,
+			ResourcePack::Zip(archive) => &archive.path,
+		}
+	}
+}
+
+impl ModelSource for ResourcePack {
+	fn load_model(&self, location: &str) -> anyhow::Result<McModelJson> {
+		let path = ResourcePack::model_path(location);
+		let data = self.read(&path)?.ok_or_else(|| McModelError::ModelNotFound {
+			location: location.to_string(),
+			searched: self.describe_path(&path),
+		})?;
+		McModelJson::parse_json_model_slice(&data)
+			.with_context(|| format!("unable to parse model {location} at {path} in {:?}", self.path()))
+	}
+}
+
+impl TextureSource for ResourcePack {
+	fn load(&self, id: &str) -> Option<RgbaImage> {
+		self.load_detailed(id).ok()
+	}
+
+	fn load_detailed(&self, id: &str) -> anyhow::Result<RgbaImage> {
+		let (data, extension) = self.texture_bytes(id)?.ok_or_else(|| McModelError::TextureNotFound {
+			id: id.to_string(),
+			searched: format!(
+				"{} with any of {TEXTURE_EXTENSIONS:?} in {:?}",
+				ResourcePack::texture_path_stem(id),
+				self.path()
+			),
+		})?;
+		decode_texture(id, &data, extension)
+	}
+
+	fn describe_location(&self, id: &str) -> Option<String> {
+		let path = self.find_texture_path(id).unwrap_or_else(|| format!("{}.png", ResourcePack::texture_path_stem(id)));
+		Some(self.describe_path(&path))
+	}
+
+	fn load_animation(&self, id: &str) -> anyhow::Result<Option<McTextureAnimation>> {
+		let Some(path) = self.find_texture_path(id) else {
+			return Ok(None);
+		};
+		let path = format!("{path}.mcmeta");
+		match self.read(&path)? {
+			Some(data) => McTextureAnimation::from_mcmeta(&data)
+				.with_context(|| format!("unable to read {}", self.describe_path(&path))),
+			None => Ok(None),
+		}
+	}
+}
+
+/// Packs stacked on top of each other the way the game applies them, the first pack that has a model or texture is
+/// the one it comes from, so overrides go before the packs they override (vanilla last)
+#[derive(Clone, Debug, Default)]
+pub struct LayeredResourcePack(pub Vec<ResourcePack>);
+
+impl LayeredResourcePack {
+	pub fn new(packs: impl IntoIterator<Item = ResourcePack>) -> LayeredResourcePack {
+		LayeredResourcePack(packs.into_iter().collect())
+	}
+
+	/// A file by its path inside the packs, from the first pack that has it
+	pub fn read(&self, path: &str) -> anyhow::Result<Option<Vec<u8>>> {
+		for pack in &self.0 {
+			if let Some(data) = pack.read(path)? {
+				return Ok(Some(data));
+			}
+		}
+		Ok(None)
+	}
+
+	/// A texture file from the first pack that has it with any extension, so a `tga` override still replaces a
+	/// lower pack's `png`
+	pub fn texture_bytes(&self, id: &str) -> anyhow::Result<Option<(Vec<u8>, &'static str)>> {
+		for pack in &self.0 {
+			if let Some(found) = pack.texture_bytes(id)? {
+				return Ok(Some(found));
+			}
+		}
+		Ok(None)
+	}
+}
+
+impl ModelSource for LayeredResourcePack {
+	fn load_model(&self, location: &str) -> anyhow::Result<McModelJson> {
+		let path = ResourcePack::model_path(location);
+		let pack = self.0.iter().find_map(|pack| pack.read(&path).transpose().map(|data| (pack, data)));
+		let (pack, data) = pack.ok_or_else(|| McModelError::ModelNotFound {
+			location: location.to_string(),
+			searched: format!("{path} in any of {} pack(s)", self.0.len()),
+		})?;
+		McModelJson::parse_json_model_slice(&data?)
+			.with_context(|| format!("unable to parse model {location} at {path} in {:?}", pack.path()))
+	}
+}
+
+impl TextureSource for LayeredResourcePack {
+	fn load(&self, id: &str) -> Option<RgbaImage> {
+		self.load_detailed(id).ok()
+	}
+
+	fn load_detailed(&self, id: &str) -> anyhow::Result<RgbaImage> {
+		let (data, extension) = self.texture_bytes(id)?.ok_or_else(|| McModelError::TextureNotFound {
+			id: id.to_string(),
+			searched: format!(
+				"{} with any of {TEXTURE_EXTENSIONS:?} in any of {} pack(s)",
+				ResourcePack::texture_path_stem(id),
+				self.0.len()
+			),
+		})?;
+		decode_texture(id, &data, extension)
+	}
+
+	/// The pack the texture comes from, or the top pack where it's missing from all of them
+	fn describe_location(&self, id: &str) -> Option<String> {
+		let found = self.0.iter().find_map(|pack| pack.find_texture_path(id).map(|path| pack.describe_path(&path)));
+		found.or_else(|| self.0.first().and_then(|pack| pack.describe_location(id)))
+	}
+
+	/// From the same pack as the texture itself, a `.mcmeta` in a pack further down doesn't go with it
+	fn load_animation(&self, id: &str) -> anyhow::Result<Option<McTextureAnimation>> {
+		self.0.iter().find(|pack| pack.find_texture_path(id).is_some()).map_or(Ok(None), |pack| pack.load_animation(id))
+	}
+}
+
+/// Same as from the filesystem, the contents win over the extension when they say what they are
+fn decode_texture(id: &str, data: &[u8], extension: &str) -> anyhow::Result<RgbaImage> {
+	let format = image::guess_format(data).ok().or_else(|| image::ImageFormat::from_extension(extension));
+	let image = match format {
+		Some(format) => image::load_from_memory_with_format(data, format),
+		None => image::load_from_memory(data),
+	};
+	Ok(image.with_context(|| format!("unable to decode texture {id} ({extension})"))?.to_rgba8())
+}
+
+/// A zip archive read fully into memory with its central directory indexed by entry name
+#[derive(Clone, Debug)]
+pub struct ZipArchive {
+	path: PathBuf,
+	data: Vec<u8>,
+	entries: HashMap<String, ZipEntry>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ZipEntry {
+	method: u16,
+	compressed_size: usize,
+	uncompressed_size: usize,
+	local_header_offset: usize,
+}
+
+const ZIP_END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const ZIP_CENTRAL_DIRECTORY_ENTRY: u32 = 0x02014b50;
+const ZIP_LOCAL_HEADER: u32 = 0x04034b50;
+const ZIP_STORED: u16 = 0;
+const ZIP_DEFLATED: u16 = 8;
+
+/// The most an entry's declared size can reserve up front, a larger entry still reads, its buffer just grows as it goes
+const ZIP_MAX_PREALLOCATION: usize = 16 << 20;
+
+impl ZipArchive {
+	pub fn open(path: impl Into<PathBuf>) -> anyhow::Result<ZipArchive> {
+		let path = path.into();
+		let data = std::fs::read(&path).with_context(|| format!("unable to read {path:?}"))?;
+		ZipArchive::from_bytes(path, data)
+	}
+
+	/// An archive that's already in memory, `path` only being what it's called in messages
+	pub fn from_bytes(path: impl Into<PathBuf>, data: Vec<u8>) -> anyhow::Result<ZipArchive> {
+		let path = path.into();
+		let entries = zip_entries(&data).with_context(|| format!("{path:?} isn't a readable zip archive"))?;
+		Ok(ZipArchive { path, data, entries })
+	}
+
+	/// Names of every file in the archive, in no particular order
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		self.entries.keys().map(String::as_str)
+	}
+
+	/// Decompresses one entry, `None` if there is no entry by that name
+	pub fn read(&self, name: &str) -> anyhow::Result<Option<Vec<u8>>> {
+		let Some(entry) = self.entries.get(name) else {
+			return Ok(None);
+		};
+		let data = self.entry_data(entry).with_context(|| format!("corrupt entry {name} in {:?}", self.path))?;
+		match entry.method {
+			ZIP_STORED => {
+				anyhow::ensure!(
+					data.len() == entry.uncompressed_size,
+					"{name} in {:?} is stored as {} bytes but says it has {}",
+					self.path,
+					data.len(),
+					entry.uncompressed_size
+				);
+				Ok(Some(data.to_vec()))
+			}
+			ZIP_DEFLATED => {
+				// Inflating no more than the entry says it holds keeps a zip bomb from going on past its header
+				let mut out = Vec::with_capacity(entry.uncompressed_size.min(ZIP_MAX_PREALLOCATION));
+				flate2::read::DeflateDecoder::new(data)
+					.take(entry.uncompressed_size as u64 + 1)
+					.read_to_end(&mut out)
+					.with_context(|| format!("unable to inflate {name} in {:?}", self.path))?;
+				anyhow::ensure!(
+					out.len() == entry.uncompressed_size,
+					"{name} in {:?} inflates to a different size than the {} bytes it says it has",
+					self.path,
+					entry.uncompressed_size
+				);
+				Ok(Some(out))
+			}
+			method => anyhow::bail!("{name} in {:?} uses unsupported compression method {method}", self.path),
+		}
+	}
+
+	fn entry_data(&self, entry: &ZipEntry) -> anyhow::Result<&[u8]> {
+		let header = entry.local_header_offset;
+		anyhow::ensure!(read_u32(&self.data, header)? == ZIP_LOCAL_HEADER, "missing local header");
+		let name_len = read_u16(&self.data, offset(header, 26)?)? as usize;
+		let extra_len = read_u16(&self.data, offset(header, 28)?)? as usize;
+		let start = offset(offset(header, 30)?, name_len + extra_len)?;
+		read_bytes(&self.data, start, entry.compressed_size)
+	}
+}
+
+fn zip_entries(data: &[u8]) -> anyhow::Result<HashMap<String, ZipEntry>> {
+	// The end record sits at the very end behind a comment of at most u16::MAX bytes
+	let search_from = data.len().saturating_sub(22 + u16::MAX as usize);
+	let end = (search_from..data.len().saturating_sub(21))
+		.rev()
+		.find(|&pos| read_u32(data, pos).ok() == Some(ZIP_END_OF_CENTRAL_DIRECTORY))
+		.context("no end of central directory record")?;
+	let count = read_u16(data, end + 10)? as usize;
+	let mut pos = read_u32(data, end + 16)? as usize;
+	anyhow::ensure!(count != u16::MAX as usize && pos != u32::MAX as usize, "zip64 archives aren't supported");
+
+	// Every entry takes at least its fixed 46 bytes, so a count that can't be there doesn't get reserved for
+	let mut entries = HashMap::with_capacity(count.min(data.len() / 46));
+	for _ in 0..count {
+		anyhow::ensure!(read_u32(data, pos)? == ZIP_CENTRAL_DIRECTORY_ENTRY, "broken central directory");
+		anyhow::ensure!(read_u16(data, offset(pos, 8)?)? & 1 == 0, "encrypted archives aren't supported");
+		let name_len = read_u16(data, offset(pos, 28)?)? as usize;
+		let extra_len = read_u16(data, offset(pos, 30)?)? as usize;
+		let comment_len = read_u16(data, offset(pos, 32)?)? as usize;
+		let name = read_bytes(data, offset(pos, 46)?, name_len)?;
+		let entry = ZipEntry {
+			method: read_u16(data, offset(pos, 10)?)?,
+			compressed_size: read_u32(data, offset(pos, 20)?)? as usize,
+			uncompressed_size: read_u32(data, offset(pos, 24)?)? as usize,
+			local_header_offset: read_u32(data, offset(pos, 42)?)? as usize,
+		};
+		// Directories are entries too but there's nothing in them to read
+		if !name.ends_with(b"/") {
+			entries.insert(String::from_utf8_lossy(name).into_owned(), entry);
+		}
+		pos = offset(pos, 46 + name_len + extra_len + comment_len)?;
+	}
+	Ok(entries)
+}
+
+/// `pos + by` without overflowing, which a 32 bit `usize` would with offsets near the end of the `u32` range
+fn offset(pos: usize, by: usize) -> anyhow::Result<usize> {
+	pos.checked_add(by).context("offset past the end of the archive")
+}
+
+/// `len` bytes of the archive from `pos` on
+fn read_bytes(data: &[u8], pos: usize, len: usize) -> anyhow::Result<&[u8]> {
+	data.get(pos..offset(pos, len)?).context("unexpected end of archive")
+}
+
+fn read_u16(data: &[u8], pos: usize) -> anyhow::Result<u16> {
+	let bytes = read_bytes(data, pos, 2)?;
+	Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> anyhow::Result<u32> {
+	let bytes = read_bytes(data, pos, 4)?;
+	Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const PACK: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/pack.zip"));
+	/// Only a blue `block/test` texture to go over the red one in `PACK`
+	const OVERRIDE: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/override.zip"));
+	const TEST_JSON: &str = "assets/minecraft/models/block/test.json";
+	const TEST_PNG: &str = "assets/minecraft/textures/block/test.png";
+
+	fn archive(data: &[u8]) -> anyhow::Result<ZipArchive> {
+		ZipArchive::from_bytes("pack.zip", data.to_vec())
+	}
+
+	fn layered() -> LayeredResourcePack {
+		let pack = |name: &str, data: &[u8]| ResourcePack::Zip(ZipArchive::from_bytes(name, data.to_vec()).unwrap());
+		LayeredResourcePack::new([pack("override.zip", OVERRIDE), pack("pack.zip", PACK)])
+	}
+
+	fn solid(image: &RgbaImage) -> Option<[u8; 4]> {
+		let first = image.get_pixel(0, 0).0;
+		image.pixels().all(|pixel| pixel.0 == first).then_some(first)
+	}
+
+	/// Where the central directory entry for `name` starts
+	fn central_entry(data: &[u8], name: &str) -> usize {
+		(0..data.len() - 46)
+			.find(|&pos| {
+				read_u32(data, pos).ok() == Some(ZIP_CENTRAL_DIRECTORY_ENTRY)
+					&& data[pos + 46..].starts_with(name.as_bytes())
+					&& read_u16(data, pos + 28).unwrap() as usize == name.len()
+			})
+			.unwrap()
+	}
+
+	fn patched(pos: usize, bytes: &[u8]) -> Vec<u8> {
+		let mut data = PACK.to_vec();
+		data[pos..pos + bytes.len()].copy_from_slice(bytes);
+		data
+	}
+
+	/// Any of `PACK` with its entries read, something broken in it has to come out as an error rather than a panic
+	fn read_everything(data: &[u8]) -> anyhow::Result<Vec<Vec<u8>>> {
+		let archive = archive(data)?;
+		let mut names: Vec<_> = archive.names().map(str::to_string).collect();
+		names.sort();
+		names.iter().map(|name| Ok(archive.read(name)?.unwrap())).collect()
+	}
+
+	#[test]
+	fn reads_stored_and_deflated_entries() {
+		let archive = archive(PACK).unwrap();
+		let mut names: Vec<_> = archive.names().collect();
+		names.sort();
+		assert_eq!(names, [TEST_JSON, "assets/minecraft/textures/block/other.png", TEST_PNG]);
+
+		let json = archive.read(TEST_JSON).unwrap().unwrap();
+		let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+		assert_eq!(json, serde_json::json!({"parent": "block/cube_all", "textures": {"all": "block/test"}}));
+		let png = image::load_from_memory(&archive.read(TEST_PNG).unwrap().unwrap()).unwrap().to_rgba8();
+		assert_eq!(png.dimensions(), (2, 2));
+		assert!(png.pixels().all(|pixel| pixel.0 == [255, 0, 0, 255]));
+
+		assert!(archive.read("assets/minecraft/models/block/missing.json").unwrap().is_none());
+		assert!(archive.read("assets/").unwrap().is_none());
+	}
+
+	#[test]
+	fn truncated_archives_fail_to_open() {
+		for len in 0..PACK.len() {
+			assert!(read_everything(&PACK[..len]).is_err(), "truncated to {len} bytes");
+		}
+	}
+
+	#[test]
+	fn corrupted_bytes_never_panic() {
+		for pos in 0..PACK.len() {
+			for byte in [0x00, 0x7F, 0xFF] {
+				let _ = read_everything(&patched(pos, &[byte]));
+			}
+		}
+	}
+
+	#[test]
+	fn oversized_fields_are_errors() {
+		let json = central_entry(PACK, TEST_JSON);
+		let png = central_entry(PACK, TEST_PNG);
+		let max = u32::MAX.to_le_bytes();
+		// Uncompressed size, compressed size and local header offset of the deflated and the stored entry
+		for entry in [json, png] {
+			for field in [24, 20, 42] {
+				let data = patched(entry + field, &max);
+				let archive = archive(&data).unwrap();
+				let name = if entry == json { TEST_JSON } else { TEST_PNG };
+				assert!(archive.read(name).is_err(), "{name} with field {field} patched");
+			}
+		}
+
+		// A name running past the end of the archive
+		assert!(archive(&patched(json + 28, &u16::MAX.to_le_bytes())).is_err());
+
+		let end = PACK.len() - 22;
+		// Central directory somewhere past the end, or claiming more entries than it has
+		assert!(archive(&patched(end + 16, &(u32::MAX - 1).to_le_bytes())).is_err());
+		assert!(archive(&patched(end + 10, &(u16::MAX - 1).to_le_bytes())).is_err());
+	}
+
+	#[test]
+	fn entries_larger_than_they_say_are_errors() {
+		// The json inflates to 73 bytes, a header saying 10 must not be trusted either way
+		let json = central_entry(PACK, TEST_JSON);
+		let archive = archive(&patched(json + 24, &10u32.to_le_bytes())).unwrap();
+		assert!(archive.read(TEST_JSON).is_err());
+	}
+
+	#[test]
+	fn top_pack_overrides_lower_ones() {
+		let packs = layered();
+		assert_eq!(solid(&packs.load_detailed("block/test").unwrap()), Some([0, 0, 255, 255]));
+		let location = packs.describe_location("block/test").unwrap();
+		assert!(location.contains("override.zip!/assets/minecraft/textures/block/test.png"));
+		// Alone the lower pack still has its own
+		assert_eq!(solid(&packs.0[1].load_detailed("block/test").unwrap()), Some([255, 0, 0, 255]));
+	}
+
+	#[test]
+	fn missing_from_the_top_pack_falls_through() {
+		let packs = layered();
+		assert_eq!(solid(&packs.load_detailed("minecraft:block/other").unwrap()), Some([0, 255, 0, 255]));
+		let location = packs.describe_location("block/other").unwrap();
+		assert!(location.contains("pack.zip!/assets/minecraft/textures/block/other.png"));
+		assert_eq!(packs.load_model("block/test").unwrap().parent.as_deref(), Some("block/cube_all"));
+		assert!(matches!(
+			packs.load_model("block/missing").unwrap_err().downcast_ref::<McModelError>(),
+			Some(McModelError::ModelNotFound { .. })
+		));
+		assert!(packs.load_detailed("block/missing").is_err());
+	}
+
+	#[test]
+	fn merged_atlas_uses_the_override() {
+		let packs = crate::templates::WithBuiltinTemplates(layered());
+		let model = packs.resolve_model("block/test").unwrap();
+		let packs = packs.0;
+		let mut atlas = crate::AtlasBuilder::new(&crate::FallbackTexture::default()).unwrap();
+		atlas.add_model_textures_from_source(&model, &packs).unwrap();
+		assert!(atlas.errors().is_empty());
+		let [x, y, width, height] = atlas.mapping("block/test").unwrap();
+		assert_eq!((width, height), (2, 2));
+		let texture = atlas.finish();
+		let three_d::TextureData::RgbaU8(texels) = &texture.data else {
+			panic!("the atlas is always rgba8");
+		};
+		for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+			assert_eq!(texels[((y + dy) * texture.width + x + dx) as usize], [0, 0, 255, 255]);
+		}
+	}
+}
@@ -0,0 +1,227 @@
+//! A small CPU rasterizer for when there is no GPU or GL driver at all (containers, CI and the like).
+//!
+//! It draws the same textured, vertex colored triangles the GPU path does, with nearest texture sampling, a depth
+//! buffer and back-face culling, it is neither fast nor lit but it's enough for correct block and item thumbnails.
+//! With no graphics context to set up it also makes for the simplest way to render at all, see [`render_bytes`].
+
+use std::collections::{HashMap, HashSet};
+
+use image::{Rgba, RgbaImage};
+use three_d::*;
+
+use crate::{templates::BuiltinTemplates, McModelGuiLight, McModelJson, McModelTransform, MeshOptions};
+
+/// How [`render_bytes`] shows the model
+#[derive(Clone, Debug)]
+pub struct RenderParams {
+	pub width: u32,
+	pub height: u32,
+	pub background: Srgba,
+	/// The model's `display` slot to show it in, looked at from the front like the game does the `gui` slot in the
+	/// inventory, `None` for the model as it is.  Blocks without a `gui` slot of their own get the usual tilted block
+	/// look for it.
+	pub display: Option<String>,
+	/// How the mesh gets built, `translucency_split` is ignored as everything gets drawn in one go
+	pub mesh: MeshOptions,
+}
+
+impl Default for RenderParams {
+	fn default() -> Self {
+		RenderParams {
+			width: 256,
+			height: 256,
+			background: Srgba::new(0, 0, 0, 0),
+			display: Some("gui".to_string()),
+			mesh: MeshOptions::default(),
+		}
+	}
+}
+
+/// Renders a model straight from its json, with `textures` handing over the image for each texture id the model uses
+/// (like `minecraft:block/stone`), so nothing needs a filesystem or a GPU, which is all a browser frontend has.
+/// Parents can only be the [`BuiltinTemplates`] as there's nothing else to load them from, and textures `textures`
+/// doesn't have get the not-found checkerboard.
+pub fn render_bytes(
+	json: &[u8],
+	textures: impl Fn(&str) -> Option<RgbaImage>,
+	params: RenderParams,
+) -> anyhow::Result<RgbaImage> {
+	let model = McModelJson::parse_json_model_slice(json)?.resolve_parents(&BuiltinTemplates)?;
+	// Fetched up front so the closure doesn't need to be shareable across the threads textures get decoded on
+	let ids = model.resolved_textures()?.into_values().collect::<HashSet<_>>();
+	let images = ids.into_iter().filter_map(|id| textures(&id).map(|image| (id, image))).collect::<HashMap<_, _>>();
+	let gui = params.display.as_deref() == Some("gui");
+	let options = MeshOptions {
+		flat_lighting: params.mesh.flat_lighting || (gui && model.gui_light() == McModelGuiLight::Front),
+		translucency_split: None,
+		..params.mesh
+	};
+	let model_mesh = model.to_cpu_mesh_with_options(&images, &options)?;
+
+	let display = match params.display.as_deref() {
+		Some(slot) => match model.display.as_ref().and_then(|display| display.slot(slot)) {
+			Some(transform) => transform.to_matrix(),
+			None if gui && !model.elements.is_empty() => McModelTransform::default_block_gui().to_matrix(),
+			None => Mat4::identity(),
+		},
+		None => Mat4::identity(),
+	};
+	let camera = Camera::new_orthographic(
+		Viewport::new_at_origo(params.width, params.height),
+		vec3(8.0, 8.0, 72.0),
+		vec3(8.0, 8.0, 8.0),
+		vec3(0.0, 1.0, 0.0),
+		16.0,
+		0.1,
+		256.0,
+	);
+	rasterize(
+		&model_mesh.mesh,
+		&model_mesh.texture,
+		camera.projection() * camera.view() * display,
+		params.width,
+		params.height,
+		params.background,
+	)
+}
+
+/// Renders `mesh` textured with `texture` (an atlas as built alongside the mesh) into a `width` by `height` image
+/// cleared to `background`.
+///
+/// `transformation` maps mesh positions straight to clip space, for a three-d [`Camera`] (which doesn't need a
+/// graphics context to create) that is `camera.projection() * camera.view() * model_transformation`. Triangles that
+/// reach behind the camera are skipped rather than clipped, and a mirroring model transformation turns the culling
+/// around and shows the inside faces instead. Translucent texels are composited over whatever is already drawn in
+/// the order the triangles come in, like the GPU path, but fully transparent ones are discarded so they don't hide
+/// anything behind them.
+pub fn rasterize(
+	mesh: &CpuMesh,
+	texture: &CpuTexture,
+	transformation: Mat4,
+	width: u32,
+	height: u32,
+	background: Srgba,
+) -> anyhow::Result<RgbaImage> {
+	let TextureData::RgbaU8(texels) = &texture.data else {
+		anyhow::bail!("only RgbaU8 textures can be rasterized");
+	};
+	anyhow::ensure!(
+		texels.len() == texture.width as usize * texture.height as usize,
+		"texture data doesn't match its {}x{} size",
+		texture.width,
+		texture.height
+	);
+	let positions = mesh.positions.to_f32();
+	let indices = mesh.indices.to_u32().unwrap_or_else(|| (0..positions.len() as u32).collect());
+	let uvs = mesh.uvs.as_ref();
+	let colors = mesh.colors.as_ref();
+
+	let mut image = RgbaImage::from_pixel(
+		width,
+		height,
+		Rgba([background.r, background.g, background.b, background.a]),
+	);
+	let mut depth = vec![f32::INFINITY; width as usize * height as usize];
+	let (fw, fh) = (width as f32, height as f32);
+
+	for triangle in indices.chunks_exact(3) {
+		let mut screen = [vec3(0.0f32, 0.0, 0.0); 3];
+		let mut inv_w = [0.0f32; 3];
+		let mut behind = false;
+		for (corner, &idx) in triangle.iter().enumerate() {
+			let clip = transformation * positions[idx as usize].extend(1.0);
+			if clip.w <= f32::EPSILON {
+				behind = true;
+				break;
+			}
+			inv_w[corner] = clip.w.recip();
+			let ndc = clip.truncate() * inv_w[corner];
+			screen[corner] = vec3((ndc.x * 0.5 + 0.5) * fw, (0.5 - ndc.y * 0.5) * fh, ndc.z);
+		}
+		if behind {
+			continue;
+		}
+		let [a, b, c] = screen;
+		let edge = |p: Vec3, q: Vec3, x: f32, y: f32| (q.x - p.x) * (y - p.y) - (q.y - p.y) * (x - p.x);
+		// Counter-clockwise front faces come out clockwise once y points down the image
+		let area = edge(a, b, c.x, c.y);
+		if area >= 0.0 {
+			continue;
+		}
+
+		let corner_uv = |corner: usize| uvs.map_or(vec2(0.0, 0.0), |uvs| uvs[triangle[corner] as usize]);
+		let corner_color = |corner: usize| {
+			colors.map_or(vec4(1.0, 1.0, 1.0, 1.0), |colors| {
+				colors[triangle[corner] as usize].to_linear_srgb()
+			})
+		};
+		let (uv, color) = (
+			[corner_uv(0), corner_uv(1), corner_uv(2)],
+			[corner_color(0), corner_color(1), corner_color(2)],
+		);
+
+		let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as u32;
+		let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as u32;
+		let max_x = (a.x.max(b.x).max(c.x).ceil().min(fw) as u32).min(width);
+		let max_y = (a.y.max(b.y).max(c.y).ceil().min(fh) as u32).min(height);
+		for y in min_y..max_y {
+			for x in min_x..max_x {
+				let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+				let weights = [edge(b, c, px, py) / area, edge(c, a, px, py) / area, edge(a, b, px, py) / area];
+				if weights.iter().any(|&w| w < 0.0) {
+					continue;
+				}
+				let z = weights[0] * a.z + weights[1] * b.z + weights[2] * c.z;
+				let pixel = y as usize * width as usize + x as usize;
+				if !(-1.0..=1.0).contains(&z) || z >= depth[pixel] {
+					continue;
+				}
+
+				// Attributes are interpolated perspective correct, which for orthographic cameras is the same thing
+				let perspective = [weights[0] * inv_w[0], weights[1] * inv_w[1], weights[2] * inv_w[2]];
+				let total = perspective[0] + perspective[1] + perspective[2];
+				let interpolate = |values: [Vec4; 3]| {
+					(values[0] * perspective[0] + values[1] * perspective[1] + values[2] * perspective[2]) / total
+				};
+				let tex_uv = interpolate(uv.map(|uv| uv.extend(0.0).extend(0.0)));
+				let tx = ((tex_uv.x * texture.width as f32) as i64).clamp(0, texture.width as i64 - 1) as usize;
+				let ty = ((tex_uv.y * texture.height as f32) as i64).clamp(0, texture.height as i64 - 1) as usize;
+				let texel = Srgba::from(texels[ty * texture.width as usize + tx]).to_linear_srgb();
+				let vertex_color = interpolate(color);
+				let alpha = texel.w * vertex_color.w;
+				if alpha <= 0.0 {
+					continue;
+				}
+				depth[pixel] = z;
+
+				let src = [
+					texel.x * vertex_color.x,
+					texel.y * vertex_color.y,
+					texel.z * vertex_color.z,
+				]
+				.map(srgb_from_linear);
+				let dst = image.get_pixel_mut(x, y);
+				dst.0 = blend(src, dst.0, alpha);
+			}
+		}
+	}
+	Ok(image)
+}
+
+pub(crate) fn srgb_from_linear(c: f32) -> f32 {
+	if c < 0.0031308 {
+		c * 12.92
+	} else {
+		1.055 * c.powf(2.4f32.recip()) - 0.055
+	}
+}
+
+/// Standard "over" blending of a 0-1 color onto a pixel, both with straight alpha, so the background's own alpha
+/// weighs in and a transparent one doesn't darken the color
+fn blend(src: [f32; 3], dst: [u8; 4], alpha: f32) -> [u8; 4] {
+	let dst_alpha = dst[3] as f32 / 255.0 * (1.0 - alpha);
+	let out_alpha = alpha + dst_alpha;
+	let to_u8 = |c: f32| (c * 255.0).round().clamp(0.0, 255.0) as u8;
+	let channel = |idx: usize| to_u8((src[idx] * alpha + dst[idx] as f32 / 255.0 * dst_alpha) / out_alpha);
+	[channel(0), channel(1), channel(2), to_u8(out_alpha)]
+}
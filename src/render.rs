@@ -0,0 +1,543 @@
+//! Offscreen rendering of built models into images.
+//!
+//! Everything here only needs a three-d [`Context`], so it works the same whether the context came from a window or
+//! from a headless GL context the caller set up themselves (for example via [`Context::from_gl_context`]), without
+//! pulling in any of the windowing dependencies. Available with the `headless` feature, which `window` also enables.
+
+use image::RgbaImage;
+use three_d::*;
+
+use crate::{McModelMesh, TextureAlpha};
+
+/// How a model's texture alpha gets drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlphaMode {
+	/// Ignore it, everything is drawn solid
+	Opaque,
+	/// Alpha testing, fragments with an alpha below the threshold are discarded and everything else is drawn solid,
+	/// which is what Minecraft does for leaves, plants and rails: crisp edges and no draw order to get wrong
+	Cutout(f32),
+	/// Standard alpha blending, needed for partially transparent textures like stained glass
+	Blend,
+}
+
+/// The alpha threshold vanilla's cutout shaders discard below
+pub const DEFAULT_ALPHA_CUTOUT: f32 = 0.1;
+
+impl AlphaMode {
+	/// The cheapest mode that still draws textures with the given alpha correctly
+	pub fn for_texture_alpha(alpha: TextureAlpha) -> AlphaMode {
+		match alpha {
+			TextureAlpha::Opaque => AlphaMode::Opaque,
+			TextureAlpha::Cutout => AlphaMode::Cutout(DEFAULT_ALPHA_CUTOUT),
+			TextureAlpha::Translucent => AlphaMode::Blend,
+		}
+	}
+}
+
+/// Uploads a built model to the GPU as something that can be rendered, drawn unlit with transparency and back-face
+/// culling the way Minecraft draws block and item models
+pub fn model_object(context: &Context, model: &McModelMesh) -> Gm<Mesh, ModelMaterial> {
+	model_object_with_alpha(context, model, AlphaMode::Blend)
+}
+
+/// Like [`model_object`] but without any blending, for models whose textures are entirely opaque, see
+/// [`McModelJson::has_transparency`](crate::McModelJson::has_transparency)
+pub fn opaque_model_object(context: &Context, model: &McModelMesh) -> Gm<Mesh, ModelMaterial> {
+	model_object_with_alpha(context, model, AlphaMode::Opaque)
+}
+
+/// Like [`model_object`] with the texture alpha drawn however `alpha` says, see
+/// [`McModelJson::texture_alpha`](crate::McModelJson::texture_alpha) for picking it from the textures
+pub fn model_object_with_alpha(context: &Context, model: &McModelMesh, alpha: AlphaMode) -> Gm<Mesh, ModelMaterial> {
+	Gm::new(Mesh::new(context, &model.mesh), ModelMaterial::new(atlas_texture(context, model), alpha))
+}
+
+/// Like [`model_object`] but for a model built with [`MeshOptions::translucency_split`](crate::MeshOptions), the
+/// opaque part comes first and is drawn without any blending, the translucent part is blended over it after. Parts
+/// without any faces are left out, models built without the split just come back as the one `model_object`.
+pub fn model_objects(context: &Context, model: &McModelMesh) -> Vec<Gm<Mesh, ModelMaterial>> {
+	let Some(translucent_mesh) = &model.translucent_mesh else {
+		return vec![model_object(context, model)];
+	};
+	let texture = atlas_texture(context, model);
+	let mut objects = Vec::new();
+	if !model.mesh.positions.is_empty() {
+		objects.push(Gm::new(Mesh::new(context, &model.mesh), ModelMaterial::new(texture.clone(), AlphaMode::Opaque)));
+	}
+	if !translucent_mesh.positions.is_empty() {
+		objects.push(Gm::new(Mesh::new(context, translucent_mesh), ModelMaterial::new(texture, AlphaMode::Blend)));
+	}
+	objects
+}
+
+/// The model lit by the lights it's rendered with rather than the shading baked into the vertex colors, so build it
+/// with [`MeshOptions::flat_lighting`](crate::MeshOptions) to not shade it twice.  With the translucency split the
+/// opaque part is drawn opaque and the translucent part blended like [`model_objects`] does, otherwise the whole mesh
+/// goes by `alpha`.  three-d's forward lit material can't alpha test, so cutout is blended instead.
+pub fn lit_model_objects(context: &Context, model: &McModelMesh, alpha: AlphaMode) -> Vec<Gm<Mesh, PhysicalMaterial>> {
+	let texture = atlas_texture(context, model);
+	let lit = |mesh: &CpuMesh, transparent: bool| {
+		let material = PhysicalMaterial {
+			name: "atlas".to_string(),
+			albedo: Srgba::WHITE,
+			albedo_texture: Some(texture.clone()),
+			metallic: 0.0,
+			roughness: 1.0,
+			metallic_roughness_texture: None,
+			occlusion_strength: 1.0,
+			occlusion_texture: None,
+			normal_scale: 1.0,
+			normal_texture: None,
+			render_states: model_render_states(transparent),
+			is_transparent: transparent,
+			emissive: Srgba::BLACK,
+			emissive_texture: None,
+			lighting_model: LightingModel::Phong,
+		};
+		Gm::new(Mesh::new(context, mesh), material)
+	};
+	let Some(translucent_mesh) = &model.translucent_mesh else {
+		return vec![lit(&model.mesh, alpha != AlphaMode::Opaque)];
+	};
+	[(&model.mesh, false), (translucent_mesh, true)]
+		.into_iter()
+		.filter(|(mesh, _)| !mesh.positions.is_empty())
+		.map(|(mesh, transparent)| lit(mesh, transparent))
+		.collect()
+}
+
+/// The atlas uploaded in linear color like three-d's own materials do it, so it blends right with the vertex colors
+fn atlas_texture(context: &Context, model: &McModelMesh) -> Texture2DRef {
+	let mut texture = model.texture.clone();
+	texture.data.to_linear_srgb();
+	Texture2DRef::from_cpu_texture(context, &texture)
+}
+
+/// Unlit textured material multiplied by the vertex colors like three-d's [`ColorMaterial`], which is all the
+/// shading block models get, but which can also do alpha testing as that only comes with three-d's lit materials
+#[derive(Clone)]
+pub struct ModelMaterial {
+	pub texture: Texture2DRef,
+	/// Fragments with an alpha below this are discarded and the rest drawn fully opaque
+	pub alpha_cutout: Option<f32>,
+	pub is_transparent: bool,
+	pub render_states: RenderStates,
+}
+
+impl ModelMaterial {
+	pub fn new(texture: Texture2DRef, alpha: AlphaMode) -> ModelMaterial {
+		ModelMaterial {
+			texture,
+			alpha_cutout: match alpha {
+				AlphaMode::Cutout(threshold) => Some(threshold),
+				AlphaMode::Opaque | AlphaMode::Blend => None,
+			},
+			is_transparent: alpha == AlphaMode::Blend,
+			render_states: model_render_states(alpha == AlphaMode::Blend),
+		}
+	}
+}
+
+/// Depth tested and written with back-face culling either way, blended or not.  Blending leaves premultiplied colors
+/// in the target, but unlike three-d's `STANDARD_TRANSPARENCY` (which just overwrites it) the alpha gets composited
+/// too, so a translucent face over a transparent background comes out partially transparent rather than darkened.
+fn model_render_states(blend: bool) -> RenderStates {
+	RenderStates {
+		write_mask: WriteMask::COLOR_AND_DEPTH,
+		depth_test: DepthTest::Less,
+		blend: if blend {
+			Blend::Enabled {
+				source_rgb_multiplier: BlendMultiplierType::SrcAlpha,
+				source_alpha_multiplier: BlendMultiplierType::One,
+				destination_rgb_multiplier: BlendMultiplierType::OneMinusSrcAlpha,
+				destination_alpha_multiplier: BlendMultiplierType::OneMinusSrcAlpha,
+				rgb_equation: BlendEquationType::Add,
+				alpha_equation: BlendEquationType::Add,
+			}
+		} else {
+			Blend::Disabled
+		},
+		cull: Cull::Back,
+	}
+}
+
+impl Material for ModelMaterial {
+	fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+		let mut shader = String::new();
+		if self.alpha_cutout.is_some() {
+			shader.push_str("#define ALPHACUT\nuniform float alphaCutout;\n");
+		}
+		shader.push_str(ColorMapping::fragment_shader_source());
+		shader.push_str(
+			"
+			uniform sampler2D tex;
+			uniform mat3 textureTransformation;
+
+			in vec2 uvs;
+			in vec4 col;
+
+			layout (location = 0) out vec4 outColor;
+
+			void main()
+			{
+				outColor = col * texture(tex, (textureTransformation * vec3(uvs, 1.0)).xy);
+				#ifdef ALPHACUT
+				if (outColor.a < alphaCutout) {
+					discard;
+				}
+				outColor.a = 1.0;
+				#endif
+				outColor.rgb = color_mapping(outColor.rgb);
+			}
+			",
+		);
+		shader
+	}
+
+	fn id(&self) -> u16 {
+		// Below the bit three-d keeps for its own materials, one id per shader variant
+		0b1u16 << 14 | self.alpha_cutout.is_some() as u16
+	}
+
+	fn fragment_attributes(&self) -> FragmentAttributes {
+		FragmentAttributes {
+			color: true,
+			uv: true,
+			..FragmentAttributes::NONE
+		}
+	}
+
+	fn use_uniforms(&self, program: &Program, camera: &Camera, _lights: &[&dyn Light]) {
+		camera.color_mapping.use_uniforms(program);
+		program.use_uniform("textureTransformation", self.texture.transformation);
+		program.use_texture("tex", &self.texture);
+		if let Some(alpha_cutout) = self.alpha_cutout {
+			program.use_uniform("alphaCutout", alpha_cutout);
+		}
+	}
+
+	fn render_states(&self) -> RenderStates {
+		self.render_states
+	}
+
+	fn material_type(&self) -> MaterialType {
+		if self.is_transparent {
+			MaterialType::Transparent
+		} else {
+			MaterialType::Opaque
+		}
+	}
+}
+
+/// Materials models are drawn with, whose culling [`set_model_transformation`] can turn around
+pub trait ModelRenderStates {
+	fn render_states_mut(&mut self) -> &mut RenderStates;
+}
+
+impl ModelRenderStates for ModelMaterial {
+	fn render_states_mut(&mut self) -> &mut RenderStates {
+		&mut self.render_states
+	}
+}
+
+impl ModelRenderStates for PhysicalMaterial {
+	fn render_states_mut(&mut self) -> &mut RenderStates {
+		&mut self.render_states
+	}
+}
+
+/// Places the model with `transformation`, a mirroring one (like a display transform with a negative scale) turns
+/// the faces inside out as far as their winding goes so those get the front faces culled instead of the back
+pub fn set_model_transformation<M: Material + ModelRenderStates>(model: &mut Gm<Mesh, M>, transformation: Mat4) {
+	model.set_transformation(transformation);
+	let mirrored = Mat3::from_cols(
+		transformation.x.truncate(),
+		transformation.y.truncate(),
+		transformation.z.truncate(),
+	)
+	.determinant()
+		< 0.0;
+	model.material.render_states_mut().cull = if mirrored { Cull::Front } else { Cull::Back };
+}
+
+/// A ground grid of thin gray lines at y=0, one line per model unit from 0 to `size` both ways, to go with three-d's
+/// [`Axes`] as reference geometry when checking where a model ends up
+pub fn grid_object(context: &Context, size: u32) -> Gm<InstancedMesh, ColorMaterial> {
+	let mut line = CpuMesh::cylinder(8);
+	line.transform(&Mat4::from_nonuniform_scale(size as f32, 0.03, 0.03)).expect("scaling is invertible");
+	let transformations = (0..=size)
+		.flat_map(|idx| {
+			let offset = idx as f32;
+			[
+				Mat4::from_translation(vec3(0.0, 0.0, offset)),
+				Mat4::from_translation(vec3(offset, 0.0, 0.0)) * Mat4::from_angle_y(degrees(-90.0)),
+			]
+		})
+		.collect();
+	let instances = Instances {
+		transformations,
+		..Default::default()
+	};
+	Gm::new(
+		InstancedMesh::new(context, &instances, &line),
+		ColorMaterial {
+			color: Srgba::new(128, 128, 128, 255),
+			..Default::default()
+		},
+	)
+}
+
+/// Clears both color and depth, with the color given as 0-255 srgba like everywhere else
+pub fn clear_state(background: Srgba) -> ClearState {
+	let [r, g, b, a] = [background.r, background.g, background.b, background.a].map(|c| c as f32 / 255.0);
+	ClearState::color_and_depth(r, g, b, a, 1.0)
+}
+
+/// A color and depth texture pair to render into and read back, kept around so that rendering many frames of the same
+/// size (like a turntable) doesn't reallocate them each time
+pub struct OffscreenTarget {
+	color: Texture2D,
+	depth: DepthTexture2D,
+	width: u32,
+	height: u32,
+}
+
+impl OffscreenTarget {
+	pub fn new(context: &Context, width: u32, height: u32) -> Self {
+		let color = Texture2D::new_empty::<[u8; 4]>(
+			context,
+			width,
+			height,
+			Interpolation::Nearest,
+			Interpolation::Nearest,
+			None,
+			Wrapping::ClampToEdge,
+			Wrapping::ClampToEdge,
+		);
+		let depth = DepthTexture2D::new::<f32>(context, width, height, Wrapping::ClampToEdge, Wrapping::ClampToEdge);
+		Self { color, depth, width, height }
+	}
+
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+
+	/// The viewport covering the whole target, set it on the camera before rendering
+	pub fn viewport(&self) -> Viewport {
+		Viewport::new_at_origo(self.width, self.height)
+	}
+
+	/// Clears the target to `background`, renders `objects` lit by `lights` (which only lit materials care about)
+	/// and reads the result back, with straight rather than premultiplied alpha like image files expect
+	pub fn render(
+		&mut self,
+		camera: &Camera,
+		objects: impl IntoIterator<Item = impl Object>,
+		lights: &[&dyn Light],
+		background: Srgba,
+	) -> RgbaImage {
+		// The target holds premultiplied colors, so the background has to go in premultiplied as well
+		let premultiply = |c: u8| (c as u16 * background.a as u16 / 255) as u8;
+		let background = Srgba::new(
+			premultiply(background.r),
+			premultiply(background.g),
+			premultiply(background.b),
+			background.a,
+		);
+		let colors = RenderTarget::new(self.color.as_color_target(None), self.depth.as_depth_target())
+			.clear(clear_state(background))
+			.render(camera, objects, lights)
+			.read_color::<[u8; 4]>();
+		let colors = colors.into_iter().flat_map(unpremultiply).collect::<Vec<u8>>();
+		RgbaImage::from_raw(self.width, self.height, colors).expect("render target matches its own size")
+	}
+
+	/// Renders `objects` without a background and keeps only how much of each pixel they cover, as white with that
+	/// coverage for its alpha
+	pub fn render_mask(&mut self, camera: &Camera, objects: impl IntoIterator<Item = impl Object>) -> RgbaImage {
+		let mut mask = self.render(camera, objects, &[], Srgba::new(0, 0, 0, 0));
+		mask.pixels_mut().for_each(|pixel| pixel.0 = [255, 255, 255, pixel.0[3]]);
+		mask
+	}
+}
+
+/// The silhouette of `model` placed with `transformation` as `camera` sees it, a mask the size of the camera's
+/// viewport that's white where the model covers and transparent elsewhere.  Transparent texels leave holes in it the
+/// way `alpha` draws them, so cutout leaves and the like outline only what's actually drawn of them.
+pub fn silhouette_mask(
+	context: &Context,
+	camera: &Camera,
+	model: &McModelMesh,
+	alpha: AlphaMode,
+	transformation: Mat4,
+) -> RgbaImage {
+	let viewport = camera.viewport();
+	let mut target = OffscreenTarget::new(context, viewport.width, viewport.height);
+	let mut camera = camera.clone();
+	camera.set_viewport(target.viewport());
+	// With the translucency split either part can cover what the other doesn't
+	let mut objects = if model.translucent_mesh.is_some() {
+		model_objects(context, model)
+	} else {
+		vec![model_object_with_alpha(context, model, alpha)]
+	};
+	objects
+		.iter_mut()
+		.for_each(|object| set_model_transformation(object, transformation));
+	target.render_mask(&camera, &objects)
+}
+
+/// The outline of [`silhouette_mask`] as polygons in viewport pixels, traced and simplified down to within
+/// `tolerance` pixels by [`trace_outline`](crate::outline::trace_outline)
+pub fn silhouette_outline(
+	context: &Context,
+	camera: &Camera,
+	model: &McModelMesh,
+	alpha: AlphaMode,
+	transformation: Mat4,
+	tolerance: f32,
+) -> Vec<Vec<Vec2>> {
+	crate::outline::trace_outline(
+		&silhouette_mask(context, camera, model, alpha, transformation),
+		0,
+		tolerance,
+	)
+}
+
+/// Divides a premultiplied color back out by its alpha, fully transparent pixels have no color left to recover
+fn unpremultiply([r, g, b, a]: [u8; 4]) -> [u8; 4] {
+	if a == 0 {
+		return [0; 4];
+	}
+	let unpremultiply = |c: u8| ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+	[unpremultiply(r), unpremultiply(g), unpremultiply(b), a]
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+	use super::*;
+	use crate::templates::BuiltinTemplates;
+	use crate::{McModelJson, MeshOptions};
+
+	/// A GL context on Mesa's software renderer, the glutin side kept alongside as it has to outlive the three-d one
+	struct SoftwareContext {
+		context: Context,
+		_gl: glutin::api::egl::context::PossiblyCurrentContext,
+	}
+
+	/// `None` where there's no software EGL device to render with, so those tests have nothing to check against
+	fn software_context() -> Option<SoftwareContext> {
+		use glutin::api::egl::{device::Device, display::Display};
+		use glutin::config::{ConfigSurfaceTypes, ConfigTemplateBuilder};
+		use glutin::context::{ContextApi, ContextAttributesBuilder, Version};
+		use glutin::prelude::*;
+
+		let mut devices = Device::query_devices().ok()?;
+		let device = devices.find(|device| device.extensions().contains("EGL_MESA_device_software"))?;
+		// No surface to draw to, everything goes into the offscreen target's textures
+		let display = unsafe { Display::with_device(&device, None) }.ok()?;
+		let template = ConfigTemplateBuilder::new().with_surface_type(ConfigSurfaceTypes::PBUFFER).build();
+		let config = unsafe { display.find_configs(template) }.ok()?.next()?;
+		let api = ContextApi::OpenGl(Some(Version::new(3, 3)));
+		let attributes = ContextAttributesBuilder::new().with_context_api(api).build(None);
+		let gl = unsafe { display.create_context(&config, &attributes) }.ok()?.make_current_surfaceless().ok()?;
+		let glow = unsafe {
+			context::Context::from_loader_function(|name| {
+				let name = std::ffi::CString::new(name).expect("GL function names have no nul");
+				display.get_proc_address(&name).cast()
+			})
+		};
+		// three-d takes the context in an `Arc` even though it's only ever used from the thread it's current on
+		#[allow(clippy::arc_with_non_send_sync)]
+		let context = Context::from_gl_context(std::sync::Arc::new(glow)).ok()?;
+		Some(SoftwareContext { context, _gl: gl })
+	}
+
+	/// `block/cross` with a texture whose columns go from opaque to fully transparent, a quarter at each of 255, 128,
+	/// 10 and 0 alpha
+	fn cross_plant() -> McModelMesh {
+		let json = br#"{"parent": "block/cross", "textures": {"cross": "block/plant"}}"#;
+		let model = McModelJson::parse_json_model_slice(json).unwrap();
+		let model = model.resolve_parents(&BuiltinTemplates).unwrap();
+		let texture = RgbaImage::from_fn(16, 16, |x, _| image::Rgba([40, 200, 40, [255, 128, 10, 0][x as usize / 4]]));
+		let textures = std::collections::HashMap::from([("block/plant".to_string(), texture)]);
+		model.to_cpu_mesh_with_options(&textures, &MeshOptions::default()).unwrap()
+	}
+
+	/// The model straight from the front, filling the image
+	fn render(context: &Context, model: &McModelMesh, alpha: AlphaMode) -> RgbaImage {
+		let mut target = OffscreenTarget::new(context, 64, 64);
+		let camera = Camera::new_orthographic(
+			target.viewport(),
+			vec3(8.0, 8.0, 72.0),
+			vec3(8.0, 8.0, 8.0),
+			vec3(0.0, 1.0, 0.0),
+			16.0,
+			0.1,
+			256.0,
+		);
+		target.render(&camera, [&model_object_with_alpha(context, model, alpha)], &[], Srgba::new(0, 0, 0, 0))
+	}
+
+	#[test]
+	fn cutout_draws_a_cross_plant_solid_or_not_at_all() {
+		let Some(gl) = software_context() else {
+			eprintln!("no software EGL device, skipping");
+			return;
+		};
+		let model = cross_plant();
+		let cutout = render(&gl.context, &model, AlphaMode::Cutout(DEFAULT_ALPHA_CUTOUT));
+		let alphas =
+			|image: &RgbaImage| image.pixels().map(|pixel| pixel.0[3]).collect::<std::collections::BTreeSet<_>>();
+		// Half transparent texels are drawn solid and the nearly transparent ones discarded, nothing in between
+		assert_eq!(alphas(&cutout), [0, 255].into());
+		let drawn = cutout.pixels().filter(|pixel| pixel.0[3] == 255).count();
+		assert!(drawn > 64 * 64 / 4, "only {drawn} pixels drawn");
+		let green = |pixel: &&image::Rgba<u8>| pixel.0[1] > pixel.0[0] && pixel.0[1] > pixel.0[2];
+		assert!(cutout.pixels().filter(|pixel| pixel.0[3] == 255).all(|pixel| green(&pixel)));
+
+		// Blending the same thing leaves the partially transparent texels partially transparent
+		let blended = render(&gl.context, &model, AlphaMode::Blend);
+		assert!(alphas(&blended).iter().any(|alpha| (1..255).contains(alpha)));
+	}
+
+	#[test]
+	fn unpremultiply_recovers_a_half_alpha_pixel() {
+		// Full red at half alpha is read back from the target as half red
+		assert_eq!(unpremultiply([128, 0, 0, 128]), [255, 0, 0, 128]);
+		assert_eq!(unpremultiply([64, 32, 0, 128]), [128, 64, 0, 128]);
+		assert_eq!(unpremultiply([255, 255, 255, 255]), [255, 255, 255, 255]);
+		// Rounding can't push anything past full
+		assert_eq!(unpremultiply([3, 0, 0, 2]), [255, 0, 0, 2]);
+		assert_eq!(unpremultiply([10, 20, 30, 0]), [0; 4]);
+	}
+
+	#[test]
+	fn translucent_faces_are_not_darkened_over_white() {
+		let Some(gl) = software_context() else {
+			eprintln!("no software EGL device, skipping");
+			return;
+		};
+		let json = br#"{"parent": "block/cube_all", "textures": {"all": "block/glass"}}"#;
+		let model = McModelJson::parse_json_model_slice(json).unwrap().resolve_parents(&BuiltinTemplates).unwrap();
+		let textures = std::collections::HashMap::from([(
+			"block/glass".to_string(),
+			RgbaImage::from_pixel(16, 16, image::Rgba([255, 0, 0, 128])),
+		)]);
+		let options = MeshOptions { flat_lighting: true, ..Default::default() };
+		let model = model.to_cpu_mesh_with_options(&textures, &options).unwrap();
+		let image = render(&gl.context, &model, AlphaMode::Blend);
+
+		// Only the south face shows head on, so it's straight red at half alpha, and over white that stays fully red
+		// with the other channels half way, nothing darker
+		let pixel = image.get_pixel(32, 32).0;
+		assert_eq!(pixel, [255, 0, 0, 128]);
+		let over_white = |c: u8| (c as u32 * pixel[3] as u32 + 255 * (255 - pixel[3] as u32)) / 255;
+		assert_eq!([0, 1, 2].map(|idx| over_white(pixel[idx])), [255, 127, 127]);
+	}
+}
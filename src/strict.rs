@@ -0,0 +1,100 @@
+//! The keys vanilla accepts at each level of a model file, for strict parsing to catch typos with.
+//!
+//! This lists what the game knows rather than what this crate models, so valid vanilla keys the crate has no use for
+//! (like `ambientocclusion` or `tintindex`) aren't reported. On top of those are the few extensions this crate reads
+//! itself, a face's `emissive` and `uvlock`, and Blockbench's own additions, which the game ignores, so that its
+//! exports don't come out full of typos.
+
+use serde_json::Value;
+
+enum Keys {
+	/// Anything goes from here on down
+	Any,
+	/// Only these keys, each with what's allowed under it
+	Object(&'static [(&'static str, Keys)]),
+	/// Every entry follows these keys
+	Array(&'static Keys),
+}
+
+const TRANSFORM: Keys = Keys::Object(&[("rotation", Keys::Any), ("translation", Keys::Any), ("scale", Keys::Any)]);
+
+const FACE: Keys = Keys::Object(&[
+	("uv", Keys::Any),
+	("texture", Keys::Any),
+	("rotation", Keys::Any),
+	("cullface", Keys::Any),
+	("tintindex", Keys::Any),
+	// Not vanilla, but read by this crate
+	("emissive", Keys::Any),
+	("uvlock", Keys::Any),
+]);
+
+const ELEMENT: Keys = Keys::Object(&[
+	("name", Keys::Any),
+	("from", Keys::Any),
+	("to", Keys::Any),
+	(
+		"rotation",
+		Keys::Object(&[("angle", Keys::Any), ("axis", Keys::Any), ("origin", Keys::Any), ("rescale", Keys::Any)]),
+	),
+	("shade", Keys::Any),
+	("light_emission", Keys::Any),
+	(
+		"faces",
+		Keys::Object(&[("north", FACE), ("east", FACE), ("south", FACE), ("west", FACE), ("up", FACE), ("down", FACE)]),
+	),
+]);
+
+const MODEL: Keys = Keys::Object(&[
+	("credit", Keys::Any),
+	("__comment", Keys::Any),
+	("texture_size", Keys::Any),
+	("groups", Keys::Any),
+	("parent", Keys::Any),
+	("ambientocclusion", Keys::Any),
+	("gui_light", Keys::Any),
+	("textures", Keys::Any),
+	(
+		"display",
+		Keys::Object(&[
+			("thirdperson_righthand", TRANSFORM),
+			("thirdperson_lefthand", TRANSFORM),
+			("firstperson_righthand", TRANSFORM),
+			("firstperson_lefthand", TRANSFORM),
+			("gui", TRANSFORM),
+			("head", TRANSFORM),
+			("ground", TRANSFORM),
+			("fixed", TRANSFORM),
+		]),
+	),
+	("elements", Keys::Array(&ELEMENT)),
+	("overrides", Keys::Array(&Keys::Object(&[("predicate", Keys::Any), ("model", Keys::Any)]))),
+]);
+
+/// Every key in a model file that vanilla doesn't know, as paths like `elements[0].faces.north.textrue`
+pub(crate) fn unknown_model_keys(model: &Value) -> Vec<String> {
+	let mut unknown = Vec::new();
+	collect_unknown(&MODEL, model, "", &mut unknown);
+	unknown
+}
+
+fn collect_unknown(keys: &Keys, value: &Value, path: &str, unknown: &mut Vec<String>) {
+	let join = |key: &str| if path.is_empty() { key.to_string() } else { format!("{path}.{key}") };
+	// Values of the wrong type are left for the actual parse to complain about
+	match (keys, value) {
+		(Keys::Object(known), Value::Object(object)) => {
+			for (key, value) in object {
+				match known.iter().find(|(name, _)| name == key) {
+					Some((_, keys)) => collect_unknown(keys, value, &join(key), unknown),
+					None => unknown.push(join(key)),
+				}
+			}
+		}
+		(Keys::Array(keys), Value::Array(array)) => {
+			for (idx, value) in array.iter().enumerate() {
+				collect_unknown(keys, value, &format!("{path}[{idx}]"), unknown);
+			}
+		}
+		_ => {}
+	}
+}
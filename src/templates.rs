@@ -0,0 +1,260 @@
+//! The most common vanilla block templates built in, so models made from them render from nothing but their own
+//! `textures` without the game's model files at hand.
+//!
+//! These are the vanilla files as they are, parents included, so resolving a model against [`BuiltinTemplates`]
+//! walks the same chain the game does (`block/cube_all` to `block/cube` to `block/block`).
+
+use anyhow::Context;
+
+use crate::{McModelError, McModelJson, McResourceLocation, ModelSource};
+
+const TEMPLATES: &[(&str, &str)] = &[
+	(
+		"block/block",
+		r##"{
+			"gui_light": "side",
+			"display": {
+				"gui": { "rotation": [30, 225, 0], "translation": [0, 0, 0], "scale": [0.625, 0.625, 0.625] },
+				"ground": { "rotation": [0, 0, 0], "translation": [0, 3, 0], "scale": [0.25, 0.25, 0.25] },
+				"fixed": { "rotation": [0, 0, 0], "translation": [0, 0, 0], "scale": [0.5, 0.5, 0.5] },
+				"thirdperson_righthand": {
+					"rotation": [75, 45, 0], "translation": [0, 2.5, 0], "scale": [0.375, 0.375, 0.375]
+				},
+				"firstperson_righthand": {
+					"rotation": [0, 45, 0], "translation": [0, 0, 0], "scale": [0.40, 0.40, 0.40]
+				},
+				"firstperson_lefthand": {
+					"rotation": [0, 225, 0], "translation": [0, 0, 0], "scale": [0.40, 0.40, 0.40]
+				}
+			}
+		}"##,
+	),
+	(
+		"block/cube",
+		r##"{
+			"parent": "block/block",
+			"elements": [
+				{
+					"from": [0, 0, 0],
+					"to": [16, 16, 16],
+					"faces": {
+						"down": { "texture": "#down", "cullface": "down" },
+						"up": { "texture": "#up", "cullface": "up" },
+						"north": { "texture": "#north", "cullface": "north" },
+						"south": { "texture": "#south", "cullface": "south" },
+						"west": { "texture": "#west", "cullface": "west" },
+						"east": { "texture": "#east", "cullface": "east" }
+					}
+				}
+			]
+		}"##,
+	),
+	(
+		"block/cube_all",
+		r##"{
+			"parent": "block/cube",
+			"textures": {
+				"particle": "#all",
+				"down": "#all",
+				"up": "#all",
+				"north": "#all",
+				"east": "#all",
+				"south": "#all",
+				"west": "#all"
+			}
+		}"##,
+	),
+	(
+		"block/cube_column",
+		r##"{
+			"parent": "block/cube",
+			"textures": {
+				"particle": "#side",
+				"down": "#end",
+				"up": "#end",
+				"north": "#side",
+				"east": "#side",
+				"south": "#side",
+				"west": "#side"
+			}
+		}"##,
+	),
+	(
+		"block/cube_bottom_top",
+		r##"{
+			"parent": "block/cube",
+			"textures": {
+				"particle": "#side",
+				"down": "#bottom",
+				"up": "#top",
+				"north": "#side",
+				"east": "#side",
+				"south": "#side",
+				"west": "#side"
+			}
+		}"##,
+	),
+	(
+		"block/orientable_with_bottom",
+		r##"{
+			"parent": "block/cube",
+			"display": {
+				"firstperson_righthand": {
+					"rotation": [0, 135, 0], "translation": [0, 0, 0], "scale": [0.40, 0.40, 0.40]
+				}
+			},
+			"textures": {
+				"particle": "#front",
+				"down": "#bottom",
+				"up": "#top",
+				"north": "#front",
+				"east": "#side",
+				"south": "#side",
+				"west": "#side"
+			}
+		}"##,
+	),
+	(
+		"block/orientable",
+		r##"{
+			"parent": "block/orientable_with_bottom",
+			"textures": {
+				"bottom": "#top"
+			}
+		}"##,
+	),
+	(
+		"block/cross",
+		r##"{
+			"ambientocclusion": false,
+			"textures": {
+				"particle": "#cross"
+			},
+			"elements": [
+				{
+					"from": [0.8, 0, 8],
+					"to": [15.2, 16, 8],
+					"rotation": { "origin": [8, 8, 8], "axis": "y", "angle": 45, "rescale": true },
+					"shade": false,
+					"faces": {
+						"north": { "uv": [0, 0, 16, 16], "texture": "#cross" },
+						"south": { "uv": [0, 0, 16, 16], "texture": "#cross" }
+					}
+				},
+				{
+					"from": [8, 0, 0.8],
+					"to": [8, 16, 15.2],
+					"rotation": { "origin": [8, 8, 8], "axis": "y", "angle": 45, "rescale": true },
+					"shade": false,
+					"faces": {
+						"west": { "uv": [0, 0, 16, 16], "texture": "#cross" },
+						"east": { "uv": [0, 0, 16, 16], "texture": "#cross" }
+					}
+				}
+			]
+		}"##,
+	),
+];
+
+/// [`ModelSource`] of the templates built into the crate, it has nothing else and fails to load any other model
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuiltinTemplates;
+
+impl BuiltinTemplates {
+	/// Every template that's built in, as paths in the `minecraft` namespace like `block/cube_all`
+	pub fn names() -> impl Iterator<Item = &'static str> {
+		TEMPLATES.iter().map(|(name, _)| *name)
+	}
+
+	/// Whether `location` is one of the built-in templates
+	pub fn contains(location: &str) -> bool {
+		Self::source(location).is_some()
+	}
+
+	fn source(location: &str) -> Option<&'static str> {
+		let location = McResourceLocation::parse(location);
+		if location.namespace != "minecraft" {
+			return None;
+		}
+		TEMPLATES.iter().find(|(name, _)| *name == location.path).map(|(_, json)| *json)
+	}
+}
+
+impl ModelSource for BuiltinTemplates {
+	fn load_model(&self, location: &str) -> anyhow::Result<McModelJson> {
+		let json = Self::source(location).ok_or_else(|| McModelError::ModelNotFound {
+			location: location.to_string(),
+			searched: "the built-in templates".to_string(),
+		})?;
+		McModelJson::parse_json_model_slice(json.as_bytes())
+			.with_context(|| format!("unable to parse built-in template {location}"))
+	}
+}
+
+/// Another [`ModelSource`] with the [`BuiltinTemplates`] to fall back on, for packs that only have their own models
+/// and rely on the game for the templates they build on.  Whatever the source has comes first, so a pack that does
+/// override a template gets its own, and a template the pack has but fails to load fails rather than being swapped
+/// for the built-in one.  Only [`McModelError::ModelNotFound`] or a not found io error count as not having it.
+#[derive(Clone, Debug)]
+pub struct WithBuiltinTemplates<S>(pub S);
+
+impl<S: ModelSource> ModelSource for WithBuiltinTemplates<S> {
+	fn load_model(&self, location: &str) -> anyhow::Result<McModelJson> {
+		match self.0.load_model(location) {
+			Err(error) if BuiltinTemplates::contains(location) && is_not_found(&error) => {
+				BuiltinTemplates.load_model(location)
+			}
+			result => result,
+		}
+	}
+}
+
+fn is_not_found(error: &anyhow::Error) -> bool {
+	matches!(error.downcast_ref::<McModelError>(), Some(McModelError::ModelNotFound { .. }))
+		|| error.downcast_ref::<std::io::Error>().is_some_and(|error| error.kind() == std::io::ErrorKind::NotFound)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A pack with a broken `block/cube` of its own and nothing else
+	struct BrokenCubePack;
+
+	impl ModelSource for BrokenCubePack {
+		fn load_model(&self, location: &str) -> anyhow::Result<McModelJson> {
+			match McResourceLocation::parse(location).path.as_str() {
+				"block/cube" => Ok(McModelJson::parse_json_model_slice(b"{\"elements\": 3}")?),
+				_ => {
+					let searched = "the test pack".to_string();
+					Err(McModelError::ModelNotFound { location: location.to_string(), searched }.into())
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn falls_back_on_templates_the_source_does_not_have() {
+		let model = WithBuiltinTemplates(BrokenCubePack).load_model("minecraft:block/cube_all").unwrap();
+		assert_eq!(model.parent.as_deref(), Some("block/cube"));
+		assert!(model.textures.contains_key("particle"));
+	}
+
+	#[test]
+	fn reports_the_source_failing_to_load_a_template_it_has() {
+		let error = WithBuiltinTemplates(BrokenCubePack).load_model("block/cube").unwrap_err();
+		assert!(matches!(error.downcast_ref::<McModelError>(), Some(McModelError::Parse(_))));
+	}
+
+	#[test]
+	fn not_found_io_errors_fall_back_too() {
+		struct Empty;
+		impl ModelSource for Empty {
+			fn load_model(&self, _location: &str) -> anyhow::Result<McModelJson> {
+				Err(std::io::Error::from(std::io::ErrorKind::NotFound).into())
+			}
+		}
+		assert_eq!(WithBuiltinTemplates(Empty).load_model("block/cube").unwrap().elements.len(), 1);
+		assert!(WithBuiltinTemplates(Empty).load_model("block/stone").is_err());
+	}
+}